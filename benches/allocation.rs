@@ -0,0 +1,125 @@
+//! Allocation churn in a soup held at a given fill: allocate a block, free
+//! random ones to make up for it, for each placement policy and for the
+//! allocation map the free list replaced. Run with `cargo bench --bench allocation`.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::Instant;
+use tierra::allocator::Placement;
+use tierra::memory::Memory;
+
+const SOUP_SIZE: usize = 65_536;
+const OPERATIONS: usize = 100_000;
+
+/// The previous allocator: a map of allocated cells, 100 random probes and
+/// then a scan from the start of the soup
+struct CellMap {
+    allocated: Vec<bool>,
+}
+
+impl CellMap {
+    fn allocate(&mut self, size: usize, rng: &mut StdRng) -> Option<usize> {
+        for _ in 0..100 {
+            let start = rng.gen_range(0..self.allocated.len());
+            if self.last_allocated(start, size).is_none() {
+                self.set(start, size, true);
+                return Some(start);
+            }
+        }
+        let mut start = 0;
+        while start < self.allocated.len() {
+            match self.last_allocated(start, size) {
+                None => {
+                    self.set(start, size, true);
+                    return Some(start);
+                }
+                Some(offset) => start += offset + 1,
+            }
+        }
+        None
+    }
+
+    fn last_allocated(&self, start: usize, size: usize) -> Option<usize> {
+        (0..size).rev().find(|&i| self.allocated[(start + i) % self.allocated.len()])
+    }
+
+    fn set(&mut self, start: usize, size: usize, allocated: bool) {
+        let len = self.allocated.len();
+        for i in 0..size {
+            self.allocated[(start + i) % len] = allocated;
+        }
+    }
+}
+
+/// Fill the soup to `fill`, then time `OPERATIONS` allocations, each
+/// followed by freeing random blocks until the soup is back at `fill`. As in
+/// the simulator, when a block does not fit, random blocks are reaped until
+/// it does.
+fn churn(name: &str, fill: f64, mut allocate: impl FnMut(usize, &mut StdRng) -> Option<usize>, mut free: impl FnMut(usize, usize)) {
+    let target = (SOUP_SIZE as f64 * fill) as usize;
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut blocks: Vec<(usize, usize)> = Vec::new();
+    let mut used = 0;
+    while used < target {
+        let size = rng.gen_range(30..120);
+        let Some(start) = allocate(size, &mut rng) else { break };
+        blocks.push((start, size));
+        used += size;
+    }
+
+    let begin = Instant::now();
+    let mut reaped = 0;
+    for _ in 0..OPERATIONS {
+        let size = rng.gen_range(30..120);
+        let start = loop {
+            if let Some(start) = allocate(size, &mut rng) {
+                break start;
+            }
+            let (start, size) = blocks.swap_remove(rng.gen_range(0..blocks.len()));
+            free(start, size);
+            used -= size;
+            reaped += 1;
+        };
+        blocks.push((start, size));
+        used += size;
+        while used > target {
+            let (start, size) = blocks.swap_remove(rng.gen_range(0..blocks.len()));
+            free(start, size);
+            used -= size;
+        }
+    }
+    let elapsed = begin.elapsed();
+    println!(
+        "{:<24} {:>9.1} ns/op  ({} blocks reaped to make room)",
+        name,
+        elapsed.as_nanos() as f64 / OPERATIONS as f64,
+        reaped
+    );
+}
+
+fn main() {
+    for fill in [0.5, 0.9, 1.0] {
+        println!("soup {:.0}% full", fill * 100.0);
+        for placement in Placement::ALL {
+            let memory = std::cell::RefCell::new(Memory::new(SOUP_SIZE));
+            let mut near = 0;
+            churn(
+                &format!("  {}", placement),
+                fill,
+                |size, rng| {
+                    let start = memory.borrow_mut().allocate(size, placement, near, rng);
+                    near = start.map_or(near, |start| start + size);
+                    start
+                },
+                |start, size| memory.borrow_mut().free(start, size),
+            );
+        }
+        let map = std::cell::RefCell::new(CellMap { allocated: vec![false; SOUP_SIZE] });
+        churn(
+            "  cell map (previous)",
+            fill,
+            |size, rng| map.borrow_mut().allocate(size, rng),
+            |start, size| map.borrow_mut().set(start, size, false),
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! Compares indexed template searches with the cell-by-cell scan.
+//! Run with `cargo bench --bench template_search`.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::hint::black_box;
+use std::time::Instant;
+use tierra::instruction::Instruction;
+use tierra::isa::Isa;
+use tierra::memory::Memory;
+use tierra::simulator::ancestor;
+
+const SOUP_SIZE: usize = 65_536;
+const MAX_SEARCH: usize = 200;
+const SEARCHES: usize = 200_000;
+
+/// A soup `fill` full of slightly mutated ancestors, the rest left empty
+fn soup(fill: f64, rng: &mut StdRng) -> Memory {
+    let genome = ancestor(Isa::TierraRs);
+    let mut memory = Memory::new(SOUP_SIZE);
+    let copies = (SOUP_SIZE as f64 * fill) as usize / genome.len();
+    let stride = SOUP_SIZE / copies.max(1);
+    for copy in 0..copies {
+        for (i, &inst) in genome.iter().enumerate() {
+            memory.write(copy * stride + i, inst);
+        }
+        for _ in 0..2 {
+            memory.write(copy * stride + rng.gen_range(0..genome.len()), Isa::TierraRs.random(rng));
+        }
+    }
+    memory
+}
+
+fn bench(name: &str, memory: &Memory, searches: &[(usize, Vec<Instruction>)], find: impl Fn(&Memory, usize, &[Instruction]) -> Option<usize>) {
+    let begin = Instant::now();
+    let mut found = 0;
+    for (start, template) in searches {
+        found += black_box(find(memory, *start, template)).is_some() as usize;
+    }
+    let elapsed = begin.elapsed();
+    println!(
+        "{:<28} {:>8.1} ns/search  ({} of {} found)",
+        name,
+        elapsed.as_nanos() as f64 / searches.len() as f64,
+        found,
+        searches.len()
+    );
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(1);
+    for fill in [0.0, 0.5, 0.9] {
+        let memory = soup(fill, &mut rng);
+        let searches: Vec<(usize, Vec<Instruction>)> = (0..SEARCHES)
+            .map(|_| {
+                let template = (0..rng.gen_range(3..=4))
+                    .map(|_| if rng.gen() { Instruction::Nop0 } else { Instruction::Nop1 })
+                    .collect();
+                (rng.gen_range(0..SOUP_SIZE), template)
+            })
+            .collect();
+
+        println!("soup {:.0}% full", fill * 100.0);
+        bench("  forward, linear scan", &memory, &searches, |m, s, t| m.find_template_forward_linear(s, t, MAX_SEARCH));
+        bench("  forward, index", &memory, &searches, |m, s, t| m.find_template_forward(s, t, MAX_SEARCH));
+        bench("  backward, linear scan", &memory, &searches, |m, s, t| m.find_template_backward_linear(s, t, MAX_SEARCH));
+        bench("  backward, index", &memory, &searches, |m, s, t| m.find_template_backward(s, t, MAX_SEARCH));
+    }
+}
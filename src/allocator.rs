@@ -0,0 +1,440 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// Where a new block is placed among the free regions of the soup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Placement {
+    /// The lowest address the block fits at
+    FirstFit,
+    /// The start of the smallest free region the block fits in
+    BestFit,
+    /// A random address, or the first address after it the block fits at
+    #[default]
+    RandomFit,
+    /// The first address the block fits at, searching forward from the
+    /// mother's block
+    NearMother,
+}
+
+impl Placement {
+    pub const ALL: [Placement; 4] = [Placement::FirstFit, Placement::BestFit, Placement::RandomFit, Placement::NearMother];
+
+    /// Short name used in config files, flags and the UI
+    pub fn name(self) -> &'static str {
+        match self {
+            Placement::FirstFit => "first",
+            Placement::BestFit => "best",
+            Placement::RandomFit => "random",
+            Placement::NearMother => "near",
+        }
+    }
+}
+
+impl fmt::Display for Placement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Placement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Placement::ALL
+            .into_iter()
+            .find(|placement| placement.name() == s)
+            .ok_or_else(|| format!("Unknown placement policy: {}", s))
+    }
+}
+
+/// The free cells of the soup as disjoint, non-adjacent regions. Blocks
+/// placed by a policy never wrap past the end of the soup; ranges reserved
+/// or released directly may.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FreeList {
+    /// Region start -> length
+    free: BTreeMap<usize, usize>,
+    /// The same regions as (length, start), for best fit
+    by_len: BTreeSet<(usize, usize)>,
+    /// Longest region starting in each bucket of addresses, for first fit
+    longest: MaxTree,
+    size: usize,
+    used: usize,
+}
+
+impl FreeList {
+    /// A soup of `size` cells, all free
+    pub fn new(size: usize) -> Self {
+        let mut list = Self {
+            free: BTreeMap::new(),
+            by_len: BTreeSet::new(),
+            longest: MaxTree::new(size.div_ceil(BUCKET)),
+            size,
+            used: 0,
+        };
+        if size > 0 {
+            list.insert(0, size);
+        }
+        list
+    }
+
+    /// Number of allocated cells
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    /// Length of the longest free region
+    pub fn largest(&self) -> usize {
+        self.by_len.last().map_or(0, |&(len, _)| len)
+    }
+
+    /// Number of free regions
+    pub fn holes(&self) -> usize {
+        self.free.len()
+    }
+
+    /// Free regions as (start, length), in address order
+    pub fn regions(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.free.iter().map(|(&start, &len)| (start, len))
+    }
+
+    /// Whether every cell of the range is free
+    pub fn is_free(&self, start: usize, len: usize) -> bool {
+        self.split(start, len).into_iter().flatten().all(|(start, end)| {
+            self.free.range(..=start).next_back().is_some_and(|(&s, &l)| s + l >= end)
+        })
+    }
+
+    /// Place a block of `len` cells, returning its start
+    pub fn allocate(&mut self, len: usize, placement: Placement, near: usize, rng: &mut impl Rng) -> Option<usize> {
+        if len == 0 || len > self.size {
+            return None;
+        }
+        let start = match placement {
+            Placement::FirstFit => self.first_fit(0, len),
+            Placement::BestFit => self.by_len.range((len, 0)..).next().map(|&(_, s)| s),
+            Placement::RandomFit => self.near_fit(len, rng.gen_range(0..self.size)),
+            Placement::NearMother => self.near_fit(len, near % self.size),
+        }?;
+        self.reserve(start, len);
+        Some(start)
+    }
+
+    /// Place a block of `len` cells starting within one of the ranges
+    /// `starts`, returning its start. Random fit picks uniformly among the
+    /// starts the block fits at; the other policies pick as `allocate` does,
+    /// among those starts only.
+    pub fn allocate_in(&mut self, len: usize, placement: Placement, near: usize, starts: &[Range<usize>], rng: &mut impl Rng) -> Option<usize> {
+        if len == 0 || len > self.size {
+            return None;
+        }
+        // Runs of starts the block fits at: (first, last, length of their region)
+        let mut fits = Vec::new();
+        for range in starts {
+            let end = range.end.min(self.size + 1 - len);
+            if range.start >= end {
+                continue;
+            }
+            let from = self.free.range(..=range.start).next_back().map_or(range.start, |(&s, _)| s);
+            for (&s, &l) in self.free.range(from..end) {
+                let first = s.max(range.start);
+                if s + l >= first + len {
+                    fits.push((first, (s + l - len).min(end - 1), l));
+                }
+            }
+        }
+        let start = match placement {
+            Placement::FirstFit => fits.iter().map(|&(first, _, _)| first).min(),
+            Placement::BestFit => fits.iter().min_by_key(|&&(first, _, l)| (l, first)).map(|&(first, _, _)| first),
+            Placement::RandomFit => {
+                let total: usize = fits.iter().map(|&(first, last, _)| last + 1 - first).sum();
+                (total > 0).then(|| {
+                    let mut pick = rng.gen_range(0..total);
+                    for &(first, last, _) in &fits {
+                        if pick <= last - first {
+                            return first + pick;
+                        }
+                        pick -= last + 1 - first;
+                    }
+                    unreachable!("pick is below the total")
+                })
+            }
+            Placement::NearMother => {
+                let near = near % self.size;
+                fits.iter()
+                    .map(|&(first, last, _)| if last >= near { first.max(near) } else { first + self.size })
+                    .min()
+                    .map(|start| start % self.size)
+            }
+        }?;
+        self.reserve(start, len);
+        Some(start)
+    }
+
+    /// The first start at or after `near` the block fits at, wrapping
+    /// around to the beginning of the soup
+    fn near_fit(&self, len: usize, near: usize) -> Option<usize> {
+        if let Some((&s, &l)) = self.free.range(..=near).next_back() {
+            if s + l >= near + len {
+                return Some(near);
+            }
+        }
+        self.first_fit(near + 1, len).or_else(|| self.first_fit(0, len))
+    }
+
+    /// Start of the first region at or after `from` that is at least `len` long
+    fn first_fit(&self, from: usize, len: usize) -> Option<usize> {
+        let mut bucket = from / BUCKET;
+        while let Some(found) = self.longest.first_at_least(bucket, len) {
+            let cells = (found * BUCKET).max(from)..(found + 1) * BUCKET;
+            if let Some((&start, _)) = self.free.range(cells).find(|&(_, &l)| l >= len) {
+                return Some(start);
+            }
+            // Only the bucket holding `from` can have its long region before it
+            bucket = found + 1;
+        }
+        None
+    }
+
+    /// Mark a free range as allocated. Returns false, changing nothing, if
+    /// any of it is already allocated.
+    pub fn reserve(&mut self, start: usize, len: usize) -> bool {
+        if len > self.size || !self.is_free(start, len) {
+            return false;
+        }
+        for (start, end) in self.split(start, len).into_iter().flatten() {
+            let (&s, &l) = self.free.range(..=start).next_back().expect("reserved range is free");
+            self.remove(s);
+            if start > s {
+                self.insert(s, start - s);
+            }
+            if s + l > end {
+                self.insert(end, s + l - end);
+            }
+            self.used += end - start;
+        }
+        true
+    }
+
+    /// Mark an allocated range as free. Releasing cells that are already
+    /// free is a bookkeeping bug in the caller; debug builds catch it.
+    pub fn release(&mut self, start: usize, len: usize) {
+        for (start, end) in self.split(start, len.min(self.size)).into_iter().flatten() {
+            let mut merged = (start, end);
+            let mut already_free = 0;
+            let touching: Vec<(usize, usize)> = self
+                .free
+                .range(..=end)
+                .rev()
+                .take_while(|&(&s, &l)| s + l >= start)
+                .map(|(&s, &l)| (s, l))
+                .collect();
+            for (s, l) in touching {
+                self.remove(s);
+                already_free += (s + l).min(end).saturating_sub(s.max(start));
+                merged = (merged.0.min(s), merged.1.max(s + l));
+            }
+            debug_assert!(already_free == 0, "released {}..{}, of which {} cells were already free", start, end, already_free);
+            self.used -= end - start - already_free;
+            self.insert(merged.0, merged.1 - merged.0);
+        }
+    }
+
+    fn insert(&mut self, start: usize, len: usize) {
+        self.free.insert(start, len);
+        self.by_len.insert((len, start));
+        self.update_bucket(start / BUCKET);
+    }
+
+    fn remove(&mut self, start: usize) {
+        if let Some(len) = self.free.remove(&start) {
+            self.by_len.remove(&(len, start));
+            self.update_bucket(start / BUCKET);
+        }
+    }
+
+    fn update_bucket(&mut self, bucket: usize) {
+        let cells = bucket * BUCKET..(bucket + 1) * BUCKET;
+        let longest = self.free.range(cells).map(|(_, &len)| len).max().unwrap_or(0);
+        self.longest.set(bucket, longest);
+    }
+
+    /// A cyclic range as at most two `start..end` ranges within the soup
+    fn split(&self, start: usize, len: usize) -> [Option<(usize, usize)>; 2] {
+        if len == 0 {
+            return [None, None];
+        }
+        let start = start % self.size;
+        if start + len <= self.size {
+            [Some((start, start + len)), None]
+        } else {
+            [Some((start, self.size)), Some((0, start + len - self.size))]
+        }
+    }
+}
+
+/// Addresses per leaf of the first-fit tree. Small enough that a bucket holds
+/// few regions, large enough that the tree stays in cache.
+const BUCKET: usize = 64;
+
+/// Segment tree over buckets holding the length of the longest free region
+/// starting in each, with each node the maximum of its children
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MaxTree {
+    nodes: Vec<usize>,
+    leaves: usize,
+}
+
+impl MaxTree {
+    fn new(size: usize) -> Self {
+        let leaves = size.next_power_of_two();
+        Self { nodes: vec![0; 2 * leaves], leaves }
+    }
+
+    fn set(&mut self, bucket: usize, len: usize) {
+        let mut node = self.leaves + bucket;
+        self.nodes[node] = len;
+        while node > 1 {
+            node /= 2;
+            self.nodes[node] = self.nodes[2 * node].max(self.nodes[2 * node + 1]);
+        }
+    }
+
+    /// Lowest bucket from `from` on where a region of at least `len` starts
+    fn first_at_least(&self, from: usize, len: usize) -> Option<usize> {
+        self.search(1, 0, self.leaves, from, len)
+    }
+
+    /// Search the node covering `lo..hi`
+    fn search(&self, node: usize, lo: usize, hi: usize, from: usize, len: usize) -> Option<usize> {
+        if hi <= from || self.nodes[node] < len {
+            return None;
+        }
+        if hi - lo == 1 {
+            return Some(lo);
+        }
+        let mid = (lo + hi) / 2;
+        self.search(2 * node, lo, mid, from, len)
+            .or_else(|| self.search(2 * node + 1, mid, hi, from, len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// Every start a block of `len` fits at without wrapping, according to
+    /// a plain map of allocated cells
+    fn fitting_starts(allocated: &[bool], len: usize) -> Vec<usize> {
+        (0..=allocated.len().saturating_sub(len))
+            .filter(|&s| s + len <= allocated.len() && !allocated[s..s + len].contains(&true))
+            .collect()
+    }
+
+    #[test]
+    fn test_blocks_never_overlap_and_policies_pick_as_specified() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..200 {
+            let size = rng.gen_range(1..200);
+            let mut list = FreeList::new(size);
+            let mut allocated = vec![false; size];
+            let mut blocks: Vec<(usize, usize)> = Vec::new();
+
+            for _ in 0..100 {
+                if !blocks.is_empty() && rng.gen_bool(0.4) {
+                    let (start, len) = blocks.swap_remove(rng.gen_range(0..blocks.len()));
+                    list.release(start, len);
+                    for i in 0..len {
+                        allocated[(start + i) % size] = false;
+                    }
+                } else if rng.gen_bool(0.1) {
+                    // A direct reservation, which may wrap
+                    let (start, len) = (rng.gen_range(0..size), rng.gen_range(1..=size.min(5)));
+                    let free = (0..len).all(|i| !allocated[(start + i) % size]);
+                    assert_eq!(list.reserve(start, len), free);
+                    if free {
+                        for i in 0..len {
+                            allocated[(start + i) % size] = true;
+                        }
+                        blocks.push((start, len));
+                    }
+                } else {
+                    let len = rng.gen_range(1..=size.min(20));
+                    let placement = Placement::ALL[rng.gen_range(0..4)];
+                    let near = rng.gen_range(0..size);
+                    let mut starts = fitting_starts(&allocated, len);
+                    // Sometimes only within a few ranges, as for a neighbourhood
+                    let ranges: Option<Vec<Range<usize>>> = rng.gen_bool(0.3).then(|| {
+                        (0..rng.gen_range(1..4))
+                            .map(|_| {
+                                let lo = rng.gen_range(0..size);
+                                lo..rng.gen_range(lo..=size)
+                            })
+                            .collect()
+                    });
+                    if let Some(ranges) = &ranges {
+                        starts.retain(|s| ranges.iter().any(|range| range.contains(s)));
+                    }
+                    let expected = match placement {
+                        Placement::FirstFit => starts.first().copied(),
+                        Placement::NearMother => starts.iter().copied().find(|&s| s >= near).or(starts.first().copied()),
+                        _ => None,
+                    };
+
+                    let start = match &ranges {
+                        Some(ranges) => list.allocate_in(len, placement, near, ranges, &mut rng),
+                        None => list.allocate(len, placement, near, &mut rng),
+                    };
+                    assert_eq!(start.is_some(), !starts.is_empty(), "{} failed to place {} cells", placement, len);
+                    let Some(start) = start else { continue };
+                    assert!(starts.contains(&start), "{} placed {} cells over allocated memory at {}", placement, len, start);
+                    if expected.is_some() {
+                        assert_eq!(Some(start), expected, "{} placed {} cells", placement, len);
+                    }
+                    if placement == Placement::BestFit {
+                        let region = |s: usize| free_region_len(&allocated, s);
+                        let best = starts.iter().map(|&s| region(s)).min().unwrap();
+                        assert_eq!(region(start), best);
+                    }
+                    for cell in &mut allocated[start..start + len] {
+                        *cell = true;
+                    }
+                    blocks.push((start, len));
+                }
+
+                assert_eq!(list.used(), allocated.iter().filter(|&&a| a).count());
+                let mut free_cells = vec![false; size];
+                let mut last_end = None;
+                for (start, len) in list.regions() {
+                    assert!(len > 0 && start + len <= size);
+                    assert!(last_end.is_none_or(|end| start > end), "free regions overlap or touch");
+                    last_end = Some(start + len);
+                    free_cells[start..start + len].fill(true);
+                }
+                assert!(free_cells.iter().zip(&allocated).all(|(&free, &used)| free != used));
+            }
+        }
+    }
+
+    /// Length of the free region starting at `start`, measured back to its
+    /// beginning and forward to its end
+    fn free_region_len(allocated: &[bool], start: usize) -> usize {
+        let begin = allocated[..start].iter().rposition(|&a| a).map_or(0, |i| i + 1);
+        let end = allocated[start..].iter().position(|&a| a).map_or(allocated.len(), |i| start + i);
+        end - begin
+    }
+
+    #[test]
+    fn test_placement_names_round_trip() {
+        for placement in Placement::ALL {
+            assert_eq!(placement.to_string().parse(), Ok(placement));
+        }
+        assert!("worst".parse::<Placement>().is_err());
+    }
+}
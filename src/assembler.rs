@@ -0,0 +1,176 @@
+use crate::instruction::Instruction;
+use crate::isa::Isa;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// A parsed genome file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Assembly {
+    /// The genome
+    pub instructions: Vec<Instruction>,
+
+    /// Label name -> offset of the instruction that follows it
+    pub labels: BTreeMap<String, usize>,
+
+    /// Instruction set named by the `.isa` directive, or the default
+    pub isa: Isa,
+}
+
+impl Assembly {
+    /// The genome, provided it was written for `isa`
+    pub fn genome_for(&self, isa: Isa) -> Result<&[Instruction], String> {
+        if self.isa == isa {
+            Ok(&self.instructions)
+        } else {
+            Err(format!("genome is written for instruction set {}, but the simulation uses {}", self.isa, isa))
+        }
+    }
+}
+
+/// Parse `.tie` assembly text.
+///
+/// The format has one mnemonic per line. `;` and `#` start comments that run
+/// to the end of the line, and a line of the form `name:` defines a label for
+/// the next instruction. A label may also prefix an instruction on the same
+/// line (`loop: nop0`). Labels are annotations only; control flow in Tierra
+/// is by template matching. A `.isa NAME` directive before the first
+/// instruction selects the instruction set whose mnemonics follow; without
+/// one the default set is assumed.
+pub fn parse(source: &str) -> Result<Assembly, String> {
+    let mut assembly = Assembly::default();
+
+    for (index, raw_line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let mut line = strip_comment(raw_line).trim();
+
+        if let Some((label, rest)) = line.split_once(':') {
+            let label = label.trim();
+            if label.is_empty() || label.contains(char::is_whitespace) {
+                return Err(format!("line {}: invalid label '{}'", line_number, label));
+            }
+            if assembly.labels.insert(label.to_string(), assembly.instructions.len()).is_some() {
+                return Err(format!("line {}: duplicate label '{}'", line_number, label));
+            }
+            line = rest.trim();
+        }
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix(".isa") {
+            if !assembly.instructions.is_empty() {
+                return Err(format!("line {}: .isa must come before the first instruction", line_number));
+            }
+            assembly.isa = name.trim().parse().map_err(|e| format!("line {}: {}", line_number, e))?;
+            continue;
+        }
+
+        let mut words = line.split_whitespace();
+        let mnemonic = words.next().unwrap_or_default();
+        if let Some(extra) = words.next() {
+            return Err(format!("line {}: unexpected '{}' after '{}'", line_number, extra, mnemonic));
+        }
+
+        let inst = assembly.isa.from_mnemonic(mnemonic)
+            .ok_or_else(|| format!("line {}: unknown mnemonic '{}'", line_number, mnemonic))?;
+        assembly.instructions.push(inst);
+    }
+
+    Ok(assembly)
+}
+
+/// Parse `.tie` assembly text into a genome for the given instruction set
+pub fn assemble(source: &str, isa: Isa) -> Result<Vec<Instruction>, String> {
+    parse(source)?.genome_for(isa).map(<[Instruction]>::to_vec)
+}
+
+/// Print a genome as `.tie` assembly, tagged with its instruction set, one
+/// mnemonic per line with its offset
+pub fn disassemble(genome: &[Instruction], isa: Isa) -> String {
+    let mut text = format!("; genome of {} instructions\n.isa {}\n", genome.len(), isa);
+    for (offset, &inst) in genome.iter().enumerate() {
+        text.push_str(&format!("{:<12}; {}\n", isa.mnemonic(inst), offset));
+    }
+    text
+}
+
+/// Read and parse a genome file
+pub fn load_assembly(path: impl AsRef<Path>) -> Result<Assembly, String> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    parse(&source).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Read and assemble a genome file written for the given instruction set
+pub fn load_genome(path: impl AsRef<Path>, isa: Isa) -> Result<Vec<Instruction>, String> {
+    let path = path.as_ref();
+    let assembly = load_assembly(path)?;
+    assembly.genome_for(isa).map(<[Instruction]>::to_vec).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Disassemble a genome into a file
+pub fn save_genome(path: impl AsRef<Path>, genome: &[Instruction], isa: Isa) -> Result<(), String> {
+    let path = path.as_ref();
+    fs::write(path, disassemble(genome, isa)).map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find([';', '#']) {
+        Some(pos) => &line[..pos],
+        None => line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Instruction::*;
+
+    #[test]
+    fn test_parse_labels_comments_and_aliases() {
+        let source = "\
+; a tiny genome
+start:
+    nop1 ; template
+    Nop1
+loop: mov_ii   # copy one cell
+    mal
+    DEC_C
+";
+        let assembly = parse(source).unwrap();
+        assert_eq!(assembly.instructions, vec![Nop1, Nop1, MovII, MallocA, DecC]);
+        assert_eq!(assembly.labels["start"], 0);
+        assert_eq!(assembly.labels["loop"], 2);
+    }
+
+    #[test]
+    fn test_parse_errors_report_line() {
+        let isa = Isa::default();
+        assert_eq!(assemble("nop0\nfrobnicate", isa).unwrap_err(), "line 2: unknown mnemonic 'frobnicate'");
+        assert!(assemble("a:\na:", isa).unwrap_err().contains("duplicate label"));
+        assert!(assemble("nop0 nop1", isa).unwrap_err().contains("unexpected"));
+        assert!(assemble("nop0\n.isa tierra0", isa).unwrap_err().contains("before the first instruction"));
+        assert!(assemble(".isa tierra9", isa).unwrap_err().contains("Unknown instruction set"));
+    }
+
+    #[test]
+    fn test_disassemble_round_trips_every_opcode() {
+        for isa in Isa::ALL {
+            let genome: Vec<Instruction> = isa.instructions().collect();
+            let text = disassemble(&genome, isa);
+            assert_eq!(parse(&text).unwrap().isa, isa);
+            assert_eq!(assemble(&text, isa).unwrap(), genome);
+        }
+    }
+
+    #[test]
+    fn test_isa_directive_selects_mnemonics() {
+        let source = ".isa tierra0\nsub_cab\nmovii\nmov_dc";
+        assert_eq!(assemble(source, Isa::Tierra0).unwrap(), vec![SubAB, MovII, CopyDC]);
+        assert!(assemble(source, Isa::TierraRs).unwrap_err().contains("written for instruction set tierra0"));
+        // Without a directive the default set's spelling applies
+        assert!(assemble("sub_cab", Isa::TierraRs).is_err());
+    }
+}
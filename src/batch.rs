@@ -0,0 +1,489 @@
+use crate::instruction::Instruction;
+use crate::metrics::{Metric, MetricsFormat, MetricsSink};
+use crate::simulator::{SimulationConfig, Simulator};
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Steps between a run's updates of the shared progress
+const PROGRESS_STEPS: u64 = 1000;
+
+/// A genome placed in every soup before a run starts, like `--genome`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeedGenome {
+    pub genome: Vec<Instruction>,
+    pub count: usize,
+    /// Where the copies are packed; `None` places each one at random
+    pub address: Option<usize>,
+}
+
+/// Replicate runs of one configuration that differ only by seed, spread over
+/// worker threads. Each run depends only on its seed, so the results do not
+/// depend on the number of threads.
+#[derive(Debug, Clone)]
+pub struct Batch {
+    /// Configuration of every run; its seed is replaced by the run's
+    pub config: SimulationConfig,
+    /// One run per seed
+    pub seeds: Vec<u64>,
+    /// Maximum number of scheduler steps per run
+    pub steps: Option<u64>,
+    /// Maximum number of executed instructions per run
+    pub instructions: Option<u64>,
+    /// Genomes to seed instead of the built-in ancestor
+    pub genomes: Vec<SeedGenome>,
+    /// Worker threads; 0 uses one per core
+    pub threads: usize,
+    /// Directory for the summary and one `run-NNN` directory per run, each
+    /// holding `metrics.csv`, `genebank.json`, `lineage.nwk` and
+    /// `result.json`; `None` writes no files
+    pub out_dir: Option<PathBuf>,
+    /// Instructions between metrics samples
+    pub metrics_every: u64,
+    /// Metrics sampled in each run and summarized across runs
+    pub metrics_fields: Vec<Metric>,
+}
+
+impl Default for Batch {
+    fn default() -> Self {
+        Self {
+            config: SimulationConfig::default(),
+            seeds: Vec::new(),
+            steps: None,
+            instructions: None,
+            genomes: Vec::new(),
+            threads: 0,
+            out_dir: None,
+            metrics_every: 100_000,
+            metrics_fields: Metric::ALL.to_vec(),
+        }
+    }
+}
+
+/// Outcome of one run
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunResult {
+    pub index: usize,
+    pub seed: u64,
+    pub steps: u64,
+    pub instructions: u64,
+    /// Whether the population died out before the run's limit
+    pub extinct: bool,
+    /// Final value of each metric, in `metrics_fields` order
+    #[serde(serialize_with = "metric_map")]
+    pub metrics: Vec<(Metric, f64)>,
+}
+
+/// Spread of one metric's final values across runs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Aggregate {
+    pub metric: &'static str,
+    pub mean: f64,
+    /// Sample standard deviation; 0 for a single run
+    pub sd: f64,
+    pub min: f64,
+    pub median: f64,
+    pub max: f64,
+}
+
+impl Aggregate {
+    fn of(metric: Metric, values: &[f64]) -> Self {
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let sd = if values.len() > 1 {
+            (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+        } else {
+            0.0
+        };
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let mid = sorted.len() / 2;
+        let median = if sorted.len() % 2 == 1 { sorted[mid] } else { (sorted[mid - 1] + sorted[mid]) / 2.0 };
+        Self { metric: metric.name(), mean, sd, min: sorted[0], median, max: sorted[sorted.len() - 1] }
+    }
+}
+
+/// Results of a whole batch
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatchSummary {
+    /// In seed order
+    pub runs: Vec<RunResult>,
+    /// Runs whose population died out
+    pub extinctions: usize,
+    /// Final metrics across all runs, extinct ones included
+    pub aggregates: Vec<Aggregate>,
+}
+
+impl BatchSummary {
+    fn new(runs: Vec<RunResult>, fields: &[Metric]) -> Self {
+        let aggregates = if runs.is_empty() {
+            Vec::new()
+        } else {
+            fields
+                .iter()
+                .enumerate()
+                .map(|(i, &metric)| Aggregate::of(metric, &runs.iter().map(|run| run.metrics[i].1).collect::<Vec<_>>()))
+                .collect()
+        };
+        Self { extinctions: runs.iter().filter(|run| run.extinct).count(), runs, aggregates }
+    }
+
+    /// One row per run: index, seed, steps, instructions, extinction and the
+    /// final metrics
+    pub fn to_csv(&self, fields: &[Metric]) -> String {
+        let mut out = String::from("run,seed,steps,instructions,extinct");
+        for metric in fields {
+            out.push(',');
+            out.push_str(metric.name());
+        }
+        out.push('\n');
+        for run in &self.runs {
+            out.push_str(&format!("{},{},{},{},{}", run.index, run.seed, run.steps, run.instructions, run.extinct));
+            for (_, value) in &run.metrics {
+                out.push_str(&format!(",{}", value));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Why a batch failed, with the outcome of every run it got to
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchError {
+    pub message: String,
+    /// Each run's result in seed order; empty if the batch never started
+    pub runs: Vec<Result<RunResult, String>>,
+}
+
+impl From<String> for BatchError {
+    fn from(message: String) -> Self {
+        Self { message, runs: Vec::new() }
+    }
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// A snapshot of a running batch's progress
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    pub total: usize,
+    pub finished: usize,
+    pub running: usize,
+    /// Instructions executed so far, summed over runs
+    pub instructions: u64,
+    /// Share of the batch done, counting each run up to its step or
+    /// instruction limit
+    pub fraction: f64,
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "runs={}/{} running={} inst={} done={:.1}% elapsed={:.0}s",
+            self.finished,
+            self.total,
+            self.running,
+            self.instructions,
+            self.fraction * 100.0,
+            self.elapsed.as_secs_f64()
+        )
+    }
+}
+
+/// Counters the workers update and the reporting thread reads
+struct Shared {
+    next: AtomicUsize,
+    finished: AtomicUsize,
+    instructions: AtomicU64,
+    /// Millionths of each run done
+    done: Vec<AtomicU64>,
+}
+
+impl Shared {
+    fn progress(&self, started: Instant) -> Progress {
+        let total = self.done.len();
+        let finished = self.finished.load(Ordering::Relaxed);
+        let done: u64 = self.done.iter().map(|d| d.load(Ordering::Relaxed)).sum();
+        Progress {
+            total,
+            finished,
+            running: self.next.load(Ordering::Relaxed).min(total) - finished,
+            instructions: self.instructions.load(Ordering::Relaxed),
+            fraction: if total == 0 { 1.0 } else { done as f64 / 1e6 / total as f64 },
+            elapsed: started.elapsed(),
+        }
+    }
+}
+
+impl Batch {
+    /// Run every seed, calling `on_progress` from the calling thread every
+    /// `report_every` and once when all runs are done. Writes
+    /// `summary.json` and `runs.csv` to `out_dir`, if set. Every run's
+    /// config is checked before any starts. A run that fails, or panics,
+    /// fails the batch once the others are done, and the error carries
+    /// every run's result.
+    pub fn run(&self, report_every: Duration, mut on_progress: impl FnMut(&Progress)) -> Result<BatchSummary, BatchError> {
+        if self.steps.is_none() && self.instructions.is_none() {
+            return Err("A batch needs a step or instruction limit".to_string().into());
+        }
+        for &seed in &self.seeds {
+            self.run_config(seed).validate().map_err(|e| format!("Seed {}: {}", seed, e))?;
+        }
+        if let Some(dir) = &self.out_dir {
+            fs::create_dir_all(dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+        }
+
+        let threads = match self.threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+        let shared = Shared {
+            next: AtomicUsize::new(0),
+            finished: AtomicUsize::new(0),
+            instructions: AtomicU64::new(0),
+            done: self.seeds.iter().map(|_| AtomicU64::new(0)).collect(),
+        };
+        let results: Mutex<Vec<Option<Result<RunResult, String>>>> = Mutex::new(vec![None; self.seeds.len()]);
+        let started = Instant::now();
+
+        thread::scope(|scope| {
+            for _ in 0..threads.min(self.seeds.len()) {
+                scope.spawn(|| loop {
+                    let index = shared.next.fetch_add(1, Ordering::Relaxed);
+                    if index >= self.seeds.len() {
+                        break;
+                    }
+                    // Configs were checked up front, but a panicking run
+                    // still fails on its own instead of leaving the
+                    // reporting loop waiting for it
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| self.run_one(index, &shared)))
+                        .unwrap_or_else(|payload| {
+                            let message = payload
+                                .downcast_ref::<&str>()
+                                .copied()
+                                .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                                .unwrap_or("unknown cause");
+                            Err(format!("Run {} (seed {}) panicked: {}", index, self.seeds[index], message))
+                        });
+                    results.lock().unwrap()[index] = Some(result);
+                    shared.finished.fetch_add(1, Ordering::Relaxed);
+                });
+            }
+
+            let mut last_report = Instant::now();
+            while shared.finished.load(Ordering::Relaxed) < self.seeds.len() {
+                thread::sleep(report_every.saturating_sub(last_report.elapsed()).clamp(Duration::from_millis(1), Duration::from_millis(50)));
+                if last_report.elapsed() >= report_every {
+                    on_progress(&shared.progress(started));
+                    last_report = Instant::now();
+                }
+            }
+        });
+        on_progress(&shared.progress(started));
+
+        let results: Vec<Result<RunResult, String>> =
+            results.into_inner().unwrap().into_iter().map(|result| result.expect("every run finished")).collect();
+        let failed: Vec<&String> = results.iter().filter_map(|result| result.as_ref().err()).collect();
+        if let Some(first) = failed.first() {
+            let message = format!("{} of {} runs failed; the first: {}", failed.len(), results.len(), first);
+            return Err(BatchError { message, runs: results });
+        }
+        let runs = results.into_iter().map(Result::unwrap).collect();
+        let summary = BatchSummary::new(runs, &self.metrics_fields);
+        if let Some(dir) = &self.out_dir {
+            write_json(&dir.join("summary.json"), &summary)?;
+            let path = dir.join("runs.csv");
+            fs::write(&path, summary.to_csv(&self.metrics_fields)).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+        }
+        Ok(summary)
+    }
+
+    /// The config of the run with `seed`
+    fn run_config(&self, seed: u64) -> SimulationConfig {
+        SimulationConfig { seed: Some(seed), ..self.config.clone() }
+    }
+
+    /// Run the seed at `index` to its limit or extinction, writing its files
+    fn run_one(&self, index: usize, shared: &Shared) -> Result<RunResult, String> {
+        let seed = self.seeds[index];
+        let mut sim = Simulator::new(self.run_config(seed)).map_err(|e| format!("Run {} (seed {}): {}", index, seed, e))?;
+        if self.genomes.is_empty() {
+            sim.initialize_with_ancestor();
+        }
+        for seed_genome in &self.genomes {
+            if sim.inoculate(&seed_genome.genome, seed_genome.address, seed_genome.count).len() < seed_genome.count {
+                return Err(format!("Run {} (seed {}): no room in the soup for all seed genomes", index, seed));
+            }
+        }
+
+        let dir = self.out_dir.as_ref().map(|dir| dir.join(format!("run-{:03}", index)));
+        let writer: Box<dyn Write> = match &dir {
+            Some(dir) => {
+                fs::create_dir_all(dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+                let path = dir.join("metrics.csv");
+                let file = File::create(&path).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+                Box::new(BufWriter::new(file))
+            }
+            None => Box::new(io::sink()),
+        };
+        let write_error = |e: io::Error| format!("Run {} (seed {}): failed to write metrics: {}", index, seed, e);
+        let mut metrics = MetricsSink::new(writer, MetricsFormat::Csv, self.metrics_fields.clone(), self.metrics_every)
+            .map_err(write_error)?;
+
+        let mut steps: u64 = 0;
+        let mut reported: u64 = 0;
+        let mut extinct = false;
+        let report = |steps: u64, instructions: u64, reported: &mut u64| {
+            let done = |count: u64, limit: Option<u64>| limit.map_or(0.0, |limit| count as f64 / limit.max(1) as f64);
+            let fraction = done(steps, self.steps).max(done(instructions, self.instructions)).min(1.0);
+            shared.done[index].store((fraction * 1e6) as u64, Ordering::Relaxed);
+            shared.instructions.fetch_add(instructions - *reported, Ordering::Relaxed);
+            *reported = instructions;
+        };
+        loop {
+            if self.steps.is_some_and(|limit| steps >= limit)
+                || self.instructions.is_some_and(|limit| sim.stats.total_instructions >= limit)
+            {
+                break;
+            }
+            sim.step();
+            steps += 1;
+            metrics.maybe_sample(&sim).map_err(write_error)?;
+            if steps.is_multiple_of(PROGRESS_STEPS) {
+                report(steps, sim.stats.total_instructions, &mut reported);
+            }
+            if sim.organisms.iter().all(|o| !o.alive) {
+                extinct = true;
+                break;
+            }
+        }
+        metrics.finish(&sim).map_err(write_error)?;
+        report(steps, sim.stats.total_instructions, &mut reported);
+        // An extinct run is done too
+        shared.done[index].store(1_000_000, Ordering::Relaxed);
+
+        let result = RunResult {
+            index,
+            seed,
+            steps,
+            instructions: sim.stats.total_instructions,
+            extinct,
+            metrics: metrics.last_values().iter().map(|&(metric, value)| (metric, value.as_f64())).collect(),
+        };
+        if let Some(dir) = &dir {
+            sim.genebank.save(dir.join("genebank.json"))?;
+            sim.lineage.save(&sim.genebank, dir.join("lineage.nwk"))?;
+            write_json(&dir.join("result.json"), &result)?;
+        }
+        Ok(result)
+    }
+}
+
+/// Serialize metrics as a map from metric name to value, in order
+fn metric_map<S: Serializer>(metrics: &[(Metric, f64)], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(metrics.iter().map(|&(metric, value)| (metric.name(), value)))
+}
+
+fn write_json(path: &std::path::Path, value: &impl Serialize) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(value).map_err(|e| format!("Failed to serialize {}: {}", path.display(), e))?;
+    fs::write(path, text).map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::topology::Topology;
+
+    fn batch(threads: usize) -> Batch {
+        Batch {
+            config: SimulationConfig { memory_size: 4096, max_population: 40, ..Default::default() },
+            seeds: vec![1, 2, 3, 4],
+            instructions: Some(30_000),
+            threads,
+            metrics_every: 10_000,
+            metrics_fields: vec![Metric::Population, Metric::Births, Metric::MeanSize],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_results_do_not_depend_on_thread_count() {
+        let mut reports = Vec::new();
+        let serial = batch(1).run(Duration::from_secs(60), |p| reports.push(p.clone())).unwrap();
+        let parallel = batch(3).run(Duration::ZERO, |_| {}).unwrap();
+        assert_eq!(serial, parallel);
+
+        // The last report is the finished batch
+        let last = reports.last().unwrap();
+        assert_eq!((last.finished, last.running, last.fraction), (4, 0, 1.0));
+        assert_eq!(last.instructions, serial.runs.iter().map(|run| run.instructions).sum::<u64>());
+
+        // Each run matches a standalone simulation with its seed
+        let mut sim = Simulator::new(SimulationConfig { seed: Some(3), ..batch(1).config }).unwrap();
+        sim.initialize_with_ancestor();
+        while sim.stats.total_instructions < 30_000 {
+            sim.step();
+        }
+        assert_eq!(serial.runs[2].seed, 3);
+        assert_eq!(serial.runs[2].metrics[1], (Metric::Births, sim.stats.total_organisms_created as f64));
+
+        let births: Vec<f64> = serial.runs.iter().map(|run| run.metrics[1].1).collect();
+        let aggregate = &serial.aggregates[1];
+        assert_eq!(aggregate.metric, "births");
+        assert!((aggregate.mean - births.iter().sum::<f64>() / 4.0).abs() < 1e-9);
+        assert!(aggregate.min <= aggregate.median && aggregate.median <= aggregate.max);
+    }
+
+    #[test]
+    fn test_writes_a_directory_per_run_and_a_summary() {
+        let dir = std::env::temp_dir().join(format!("tierra-batch-{}", std::process::id()));
+        let summary = Batch { out_dir: Some(dir.clone()), seeds: vec![5, 6], ..batch(2) }.run(Duration::ZERO, |_| {}).unwrap();
+
+        for run in ["run-000", "run-001"] {
+            for file in ["metrics.csv", "genebank.json", "lineage.nwk", "result.json"] {
+                assert!(dir.join(run).join(file).is_file(), "{}/{} missing", run, file);
+            }
+        }
+        let metrics = fs::read_to_string(dir.join("run-001/metrics.csv")).unwrap();
+        assert_eq!(metrics.lines().next(), Some("population,births,mean_size"));
+        let result: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("run-001/result.json")).unwrap()).unwrap();
+        assert_eq!(result["seed"], 6);
+        assert_eq!(result["metrics"]["births"].as_f64(), Some(summary.runs[1].metrics[1].1));
+
+        let runs = fs::read_to_string(dir.join("runs.csv")).unwrap();
+        assert_eq!(runs.lines().count(), 3);
+        assert!(runs.starts_with("run,seed,steps,instructions,extinct,population,births,mean_size\n0,5,"));
+        let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("summary.json")).unwrap()).unwrap();
+        assert_eq!(written["aggregates"][0]["metric"], "population");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_an_invalid_config_fails_before_any_run_starts() {
+        let topology = Topology::Torus { width: 0, radius: 2 };
+        let broken = Batch { config: SimulationConfig { topology, ..batch(2).config }, ..batch(2) };
+        let mut reports = 0;
+        let error = broken.run(Duration::ZERO, |_| reports += 1).unwrap_err();
+        assert!(error.message.starts_with("Seed 1: Torus width"), "{}", error);
+        assert!(error.runs.is_empty());
+        assert_eq!(reports, 0);
+
+        let broken = Batch { config: SimulationConfig { crossover_rate: f64::NAN, ..batch(2).config }, ..batch(2) };
+        let error = broken.run(Duration::ZERO, |_| {}).unwrap_err();
+        assert_eq!(error.message, "Seed 1: Crossover rate must be between 0 and 1, not NaN");
+    }
+}
@@ -0,0 +1,16 @@
+use tierra::headless::{self, HeadlessOptions};
+
+fn main() {
+    let code = match HeadlessOptions::parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => headless::run(&options),
+        Ok(None) => {
+            println!("{}", headless::USAGE);
+            headless::EXIT_OK
+        }
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, headless::USAGE);
+            headless::EXIT_USAGE
+        }
+    };
+    std::process::exit(code);
+}
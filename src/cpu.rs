@@ -1,18 +1,50 @@
 use crate::instruction::Instruction;
+use crate::isa::Isa;
 use crate::memory::Memory;
+use crate::mutation::MutationKind;
 use crate::organism::Organism;
+use crate::permissions::{AccessKind, Permissions};
+use crate::profile::Profile;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 /// The CPU that executes organism instructions
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CPU {
     /// Maximum search distance for template matching
     pub max_search: usize,
+
+    /// Instruction set that opcodes are decoded and mutations drawn from
+    pub isa: Isa,
+
+    /// Probability that a MovII or MovCD write lands a random instruction instead
+    pub copy_mutation_rate: f64,
+
+    /// Probability that an arithmetic instruction is off by one
+    pub flaw_rate: f64,
+
+    /// What organisms may read, write and execute in each memory region
+    pub permissions: Permissions,
+
+    /// Addresses outside the organism's own block where the last instruction
+    /// was fetched or matched a template, for parasitism detection
+    #[serde(skip)]
+    pub foreign_code: Vec<usize>,
+
+    /// Executions, errors and template searches per instruction and genotype
+    pub profile: Profile,
 }
 
 impl CPU {
     pub fn new() -> Self {
         Self {
             max_search: 200, // Maximum distance to search for templates
+            isa: Isa::default(),
+            copy_mutation_rate: 0.0,
+            flaw_rate: 0.0,
+            permissions: Permissions::default(),
+            foreign_code: Vec::new(),
+            profile: Profile::new(),
         }
     }
 
@@ -22,14 +54,28 @@ impl CPU {
         &mut self,
         organism: &mut Organism,
         memory: &mut Memory,
-        _rng: &mut impl Rng,
+        rng: &mut impl Rng,
     ) -> ExecutionResult {
         if !organism.alive {
             return ExecutionResult::Dead;
         }
 
+        self.foreign_code.clear();
+        if !self.allowed(organism, memory, organism.ip, AccessKind::Execute) {
+            // Execution strayed somewhere it may not run; restart at the own block
+            organism.errors += 1;
+            organism.set_ip(organism.address, memory.size());
+            return ExecutionResult::Continue;
+        }
+        if !organism.is_own_address(organism.ip, memory.size()) {
+            self.foreign_code.push(organism.ip);
+        }
+
         let inst = memory.read(organism.ip);
+        let errors = organism.errors;
+        self.profile.record_execution(inst, organism.genotype);
         let mut advance_ip = true;
+        let mut mutation = None;
 
         match inst {
             Instruction::Nop0 | Instruction::Nop1 => {
@@ -39,78 +85,115 @@ impl CPU {
             Instruction::IfCZ => {
                 // If CX is zero, execute next instruction, otherwise skip it
                 if organism.cx != 0 {
-                    organism.increment_ip();
+                    organism.increment_ip(memory.size());
                 }
             }
 
             Instruction::JmpB => {
                 // Jump backward to template complement
-                organism.increment_ip();
+                organism.increment_ip(memory.size());
                 let template = self.read_template(organism, memory);
 
-                if let Some(addr) = memory.find_template_backward(organism.ip, &template, self.max_search) {
-                    organism.set_ip(addr);
-                    advance_ip = false;
-                } else {
-                    organism.errors += 1;
+                match self.find_template(inst, organism, memory, &template, Search::Backward) {
+                    Some(addr) if self.allowed(organism, memory, addr, AccessKind::Execute) => {
+                        self.note_template(organism, memory, addr, template.len());
+                        organism.set_ip(addr, memory.size());
+                        advance_ip = false;
+                    }
+                    _ => organism.errors += 1,
                 }
             }
 
             Instruction::JmpF => {
                 // Jump forward to template complement
-                organism.increment_ip();
+                organism.increment_ip(memory.size());
                 let template = self.read_template(organism, memory);
 
-                if let Some(addr) = memory.find_template_forward(organism.ip, &template, self.max_search) {
-                    organism.set_ip(addr);
-                    advance_ip = false;
-                } else {
-                    organism.errors += 1;
+                match self.find_template(inst, organism, memory, &template, Search::Forward) {
+                    Some(addr) if self.allowed(organism, memory, addr, AccessKind::Execute) => {
+                        self.note_template(organism, memory, addr, template.len());
+                        organism.set_ip(addr, memory.size());
+                        advance_ip = false;
+                    }
+                    _ => organism.errors += 1,
+                }
+            }
+
+            Instruction::JmpO => {
+                // Jump to the nearest template complement, searching outward
+                organism.increment_ip(memory.size());
+                let template = self.read_template(organism, memory);
+
+                match self.find_template(inst, organism, memory, &template, Search::Nearest) {
+                    Some(addr) if self.allowed(organism, memory, addr, AccessKind::Execute) => {
+                        self.note_template(organism, memory, addr, template.len());
+                        organism.set_ip(addr, memory.size());
+                        advance_ip = false;
+                    }
+                    _ => organism.errors += 1,
                 }
             }
 
             Instruction::Call => {
                 // Call procedure at template
-                organism.increment_ip();
+                organism.increment_ip(memory.size());
                 let template = self.read_template(organism, memory);
 
-                if let Some(addr) = memory.find_template_forward(organism.ip, &template, self.max_search) {
-                    if organism.push(organism.ip).is_ok() {
-                        organism.set_ip(addr);
-                        advance_ip = false;
+                match self.find_template(inst, organism, memory, &template, Search::Forward) {
+                    Some(addr) if self.allowed(organism, memory, addr, AccessKind::Execute) => {
+                        self.note_template(organism, memory, addr, template.len());
+                        if organism.push(organism.ip).is_ok() {
+                            organism.set_ip(addr, memory.size());
+                            advance_ip = false;
+                        }
                     }
-                } else {
-                    organism.errors += 1;
+                    _ => organism.errors += 1,
                 }
             }
 
             Instruction::Ret => {
                 // Return from procedure
-                if let Ok(addr) = organism.pop() {
-                    organism.set_ip(addr);
-                    advance_ip = false;
+                match organism.pop() {
+                    Ok(addr) if self.allowed(organism, memory, addr, AccessKind::Execute) => {
+                        organism.set_ip(addr, memory.size());
+                        advance_ip = false;
+                    }
+                    Ok(_) => organism.errors += 1,
+                    Err(_) => {}
                 }
             }
 
             Instruction::MovDC => {
-                // Move data from [CX] to DX
-                let addr = organism.address + (organism.cx % organism.size);
-                organism.dx = memory.read(addr).to_u8() as usize;
+                // Move data from [CX] to DX, subject to read permission
+                let addr = memory.normalize_addr(organism.cx);
+                if self.allowed(organism, memory, addr, AccessKind::Read) {
+                    organism.dx = self.isa.encode(memory.read(addr)).unwrap_or(0);
+                } else {
+                    organism.errors += 1;
+                }
             }
 
             Instruction::MovCD => {
-                // Move data from DX to [CX]
-                let addr = organism.address + (organism.cx % organism.size);
-                let inst = Instruction::from_u8((organism.dx % 27) as u8);
+                // Move data from DX to [CX], subject to write permission; a
+                // copy made this way can suffer copy errors like MovII
+                let addr = memory.normalize_addr(organism.cx);
+                let inst = self.isa.decode(organism.dx % self.isa.opcode_count()).unwrap_or_default();
 
-                // Only allow writing within organism's own memory
-                if organism.is_address_valid(addr) {
+                if self.allowed(organism, memory, addr, AccessKind::Write) {
                     memory.write(addr, inst);
+                    organism.record_daughter_write(addr, memory.size());
+                    if memory.maybe_mutate(addr, self.copy_mutation_rate, self.isa, rng) {
+                        mutation = Some(MutationKind::CopyError);
+                    }
                 } else {
                     organism.errors += 1;
                 }
             }
 
+            Instruction::MovAB => organism.ax = organism.bx,
+            Instruction::MovBA => organism.bx = organism.ax,
+            Instruction::CopyDC => organism.dx = organism.cx,
+
             Instruction::Adr => {
                 // Get current address
                 organism.ax = organism.ip;
@@ -118,10 +201,11 @@ impl CPU {
 
             Instruction::AdrB => {
                 // Address of nearest template backward
-                organism.increment_ip();
+                organism.increment_ip(memory.size());
                 let template = self.read_template(organism, memory);
 
-                if let Some(addr) = memory.find_template_backward(organism.ip, &template, self.max_search) {
+                if let Some(addr) = self.find_template(inst, organism, memory, &template, Search::Backward) {
+                    self.note_template(organism, memory, addr, template.len());
                     organism.ax = addr;
                 } else {
                     organism.errors += 1;
@@ -131,10 +215,11 @@ impl CPU {
 
             Instruction::AdrF => {
                 // Address of nearest template forward
-                organism.increment_ip();
+                organism.increment_ip(memory.size());
                 let template = self.read_template(organism, memory);
 
-                if let Some(addr) = memory.find_template_forward(organism.ip, &template, self.max_search) {
+                if let Some(addr) = self.find_template(inst, organism, memory, &template, Search::Forward) {
+                    self.note_template(organism, memory, addr, template.len());
                     organism.ax = addr;
                 } else {
                     organism.errors += 1;
@@ -142,10 +227,37 @@ impl CPU {
                 advance_ip = false;
             }
 
-            Instruction::IncA => organism.ax = organism.ax.wrapping_add(1) % memory.size(),
-            Instruction::IncB => organism.bx = organism.bx.wrapping_add(1) % memory.size(),
-            Instruction::IncC => organism.cx = organism.cx.wrapping_add(1) % memory.size(),
-            Instruction::DecC => organism.cx = if organism.cx > 0 { organism.cx - 1 } else { memory.size() - 1 },
+            Instruction::AdrO => {
+                // Address of nearest template, searching outward
+                organism.increment_ip(memory.size());
+                let template = self.read_template(organism, memory);
+
+                if let Some(addr) = self.find_template(inst, organism, memory, &template, Search::Nearest) {
+                    self.note_template(organism, memory, addr, template.len());
+                    organism.ax = addr;
+                } else {
+                    organism.errors += 1;
+                }
+                advance_ip = false;
+            }
+
+            Instruction::IncA => organism.ax = wrap_add(organism.ax, 1 + self.flaw(rng, &mut mutation), memory.size()),
+            Instruction::IncB => organism.bx = wrap_add(organism.bx, 1 + self.flaw(rng, &mut mutation), memory.size()),
+            Instruction::IncC => organism.cx = wrap_add(organism.cx, 1 + self.flaw(rng, &mut mutation), memory.size()),
+            Instruction::DecC => organism.cx = wrap_add(organism.cx, -1 + self.flaw(rng, &mut mutation), memory.size()),
+            Instruction::SubAB => {
+                let delta = self.flaw(rng, &mut mutation) - organism.bx as isize;
+                organism.cx = wrap_add(organism.ax, delta, memory.size());
+            }
+            Instruction::SubAC => {
+                let delta = self.flaw(rng, &mut mutation) - organism.cx as isize;
+                organism.ax = wrap_add(organism.ax, delta, memory.size());
+            }
+            Instruction::Shl => {
+                organism.cx = wrap_add(organism.cx * 2, self.flaw(rng, &mut mutation), memory.size());
+            }
+            Instruction::Not0 => organism.cx = (organism.cx ^ 1) % memory.size(),
+            Instruction::Zero => organism.cx = 0,
 
             Instruction::MallocA => {
                 // Allocate memory block of size AX
@@ -171,13 +283,90 @@ impl CPU {
                 organism.kill();
                 return ExecutionResult::Dead;
             }
+
+            Instruction::MovII => {
+                // Copy [AX] to [BX], subject to read and write permissions
+                let src = memory.normalize_addr(organism.ax);
+                let dst = memory.normalize_addr(organism.bx);
+                if self.allowed(organism, memory, src, AccessKind::Read)
+                    && self.allowed(organism, memory, dst, AccessKind::Write)
+                {
+                    memory.write(dst, memory.read(src));
+                    organism.record_daughter_write(dst, memory.size());
+                    if memory.maybe_mutate(dst, self.copy_mutation_rate, self.isa, rng) {
+                        mutation = Some(MutationKind::CopyError);
+                    }
+                } else {
+                    organism.errors += 1;
+                }
+            }
         }
 
         if advance_ip {
-            organism.increment_ip();
+            organism.increment_ip(memory.size());
+        }
+        if organism.errors > errors {
+            self.profile.record_error(inst, organism.genotype);
+        }
+
+        match mutation {
+            Some(kind) => ExecutionResult::Mutated(kind),
+            None => ExecutionResult::Continue,
         }
+    }
 
-        ExecutionResult::Continue
+    /// Off-by-one error for an arithmetic result: -1 or +1 with probability
+    /// `flaw_rate`, otherwise 0. Records the flaw in `mutation`.
+    fn flaw(&self, rng: &mut impl Rng, mutation: &mut Option<MutationKind>) -> isize {
+        if self.flaw_rate > 0.0 && rng.gen::<f64>() < self.flaw_rate {
+            *mutation = Some(MutationKind::Flaw);
+            if rng.gen() { 1 } else { -1 }
+        } else {
+            0
+        }
+    }
+
+    /// Whether the permissions let `organism` make an access of `kind` at `addr`
+    fn allowed(&self, organism: &Organism, memory: &Memory, addr: usize, kind: AccessKind) -> bool {
+        self.permissions.allows(organism, memory.normalize_addr(addr), memory.size(), kind)
+    }
+
+    /// Search for the complement of `template` from the IP, recording the
+    /// search in the profile of `inst`. A complement the organism may not
+    /// read counts as not found.
+    fn find_template(
+        &mut self,
+        inst: Instruction,
+        organism: &Organism,
+        memory: &Memory,
+        template: &[Instruction],
+        search: Search,
+    ) -> Option<usize> {
+        let start = organism.ip;
+        let found = match search {
+            Search::Backward => memory.find_template_backward(start, template, self.max_search),
+            Search::Forward => memory.find_template_forward(start, template, self.max_search),
+            Search::Nearest => memory.find_template_nearest(start, template, self.max_search),
+        };
+        let found = found.filter(|&end| {
+            (1..=template.len()).all(|back| self.allowed(organism, memory, end + memory.size() - back, AccessKind::Read))
+        });
+        // Distance from the template to the first cell of the complement
+        let distance = found.map(|end| {
+            let size = memory.size();
+            memory.distance(start, (end + size - template.len()) % size)
+        });
+        self.profile.record_search(inst, organism.genotype, distance);
+        found
+    }
+
+    /// Record a template match found outside the organism's own block.
+    /// `end` is the address just past the matched complement.
+    fn note_template(&mut self, organism: &Organism, memory: &Memory, end: usize, len: usize) {
+        let start = memory.normalize_addr(end + memory.size() - len);
+        if !organism.is_own_address(start, memory.size()) {
+            self.foreign_code.push(start);
+        }
     }
 
     /// Read a template starting at the current IP
@@ -205,11 +394,107 @@ impl Default for CPU {
     }
 }
 
+/// Direction of a template search
+#[derive(Debug, Clone, Copy)]
+enum Search {
+    Backward,
+    Forward,
+    Nearest,
+}
+
+/// Add a signed delta to a register, wrapping within memory size
+fn wrap_add(value: usize, delta: isize, modulus: usize) -> usize {
+    (value as isize + delta).rem_euclid(modulus as isize) as usize
+}
+
 /// Result of executing an instruction
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ExecutionResult {
     Continue,       // Continue execution
+    Mutated(MutationKind), // Continue execution; the instruction was hit by a mutation
     Dead,          // Organism is dead
     Malloc(usize), // Request memory allocation
     Divide,        // Request division (create offspring)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// Run `inst` once in a 100-cell soup for an organism with the given
+    /// registers, returning the organism afterwards
+    fn run(inst: Instruction, ax: usize, bx: usize, cx: usize) -> Organism {
+        let mut cpu = CPU::new();
+        let mut memory = Memory::new(100);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut organism = Organism::new(0, 10, 5, 0, None);
+        organism.ax = ax;
+        organism.bx = bx;
+        organism.cx = cx;
+        memory.write(10, inst);
+        assert_eq!(cpu.execute_instruction(&mut organism, &mut memory, &mut rng), ExecutionResult::Continue);
+        assert_eq!(organism.ip, 11);
+        organism
+    }
+
+    #[test]
+    fn test_arithmetic_and_register_opcodes() {
+        // SubAB: CX = AX - BX, wrapping around the soup
+        assert_eq!(run(Instruction::SubAB, 30, 12, 0).cx, 18);
+        assert_eq!(run(Instruction::SubAB, 5, 12, 0).cx, 93);
+
+        // SubAC: AX = AX - CX
+        let org = run(Instruction::SubAC, 30, 0, 4);
+        assert_eq!((org.ax, org.cx), (26, 4));
+        assert_eq!(run(Instruction::SubAC, 2, 0, 4).ax, 98);
+
+        assert_eq!(run(Instruction::Zero, 0, 0, 42).cx, 0);
+
+        // Not0 flips the low bit of CX
+        assert_eq!(run(Instruction::Not0, 0, 0, 0).cx, 1);
+        assert_eq!(run(Instruction::Not0, 0, 0, 7).cx, 6);
+
+        // Shl doubles CX
+        assert_eq!(run(Instruction::Shl, 0, 0, 3).cx, 6);
+        assert_eq!(run(Instruction::Shl, 0, 0, 60).cx, 20);
+
+        let org = run(Instruction::MovAB, 1, 2, 0);
+        assert_eq!((org.ax, org.bx), (2, 2));
+        let org = run(Instruction::MovBA, 1, 2, 0);
+        assert_eq!((org.ax, org.bx), (1, 1));
+
+        // CopyDC: DX = CX
+        let mut cpu = CPU::new();
+        let mut memory = Memory::new(100);
+        let mut organism = Organism::new(0, 10, 5, 0, None);
+        organism.cx = 9;
+        memory.write(10, Instruction::CopyDC);
+        cpu.execute_instruction(&mut organism, &mut memory, &mut StdRng::seed_from_u64(0));
+        assert_eq!(organism.dx, 9);
+    }
+
+    #[test]
+    fn test_movcd_writes_suffer_copy_errors() {
+        let mut changed = 0;
+        for seed in 0..50 {
+            let mut cpu = CPU { copy_mutation_rate: 1.0, ..CPU::new() };
+            let mut memory = Memory::new(100);
+            let mut organism = Organism::new(0, 10, 5, 0, None);
+            organism.cx = 12;
+            organism.dx = cpu.isa.encode(Instruction::IncA).unwrap();
+            memory.write(10, Instruction::MovCD);
+            let result = cpu.execute_instruction(&mut organism, &mut memory, &mut StdRng::seed_from_u64(seed));
+
+            // Only a write that really changed the cell counts as a copy error
+            if memory.read(12) == Instruction::IncA {
+                assert_eq!(result, ExecutionResult::Continue);
+            } else {
+                assert_eq!(result, ExecutionResult::Mutated(MutationKind::CopyError));
+                changed += 1;
+            }
+        }
+        assert!(changed > 40, "Only {changed} of 50 writes were mutated");
+    }
+}
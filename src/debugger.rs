@@ -0,0 +1,164 @@
+use crate::cpu::ExecutionResult;
+use crate::instruction::Instruction;
+use crate::simulator::Simulator;
+
+/// Where the debugger should stop before executing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Absolute soup address of the instruction pointer
+    Address(usize),
+    /// Any occurrence of this instruction at the instruction pointer
+    Instruction(Instruction),
+}
+
+impl Breakpoint {
+    fn hits(&self, ip: usize, inst: Instruction) -> bool {
+        match *self {
+            Breakpoint::Address(addr) => addr == ip,
+            Breakpoint::Instruction(target) => target == inst,
+        }
+    }
+}
+
+/// Why execution of the debugged organism stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// A single step completed
+    Stepped,
+    /// The next instruction matches a breakpoint
+    Breakpoint(Breakpoint),
+    /// The organism executed Divide
+    Divided,
+    /// The organism died or no longer exists
+    Dead,
+    /// The instruction budget ran out
+    Limit,
+}
+
+/// Single-organism debugger. Instructions run through the simulator's normal
+/// execution path, so allocation, division and mutation behave exactly as in
+/// a scheduled time slice; the rest of the soup is paused meanwhile.
+#[derive(Debug, Clone, Default)]
+pub struct Debugger {
+    /// ID of the organism being debugged
+    pub organism: Option<usize>,
+    pub breakpoints: Vec<Breakpoint>,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a breakpoint, or remove it if it is already set
+    pub fn toggle_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if let Some(pos) = self.breakpoints.iter().position(|&b| b == breakpoint) {
+            self.breakpoints.remove(pos);
+        } else {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+
+    /// Execute one instruction of the selected organism
+    pub fn step(&self, sim: &mut Simulator) -> StopReason {
+        match self.organism.and_then(|id| sim.step_organism(id)) {
+            None | Some(ExecutionResult::Dead) => StopReason::Dead,
+            Some(ExecutionResult::Divide) => StopReason::Divided,
+            Some(_) => StopReason::Stepped,
+        }
+    }
+
+    /// Run the selected organism until it divides, dies, reaches a
+    /// breakpoint or has executed `limit` instructions. The instruction
+    /// under the IP when called never triggers a breakpoint, so repeated
+    /// calls make progress.
+    pub fn run_until_divide(&self, sim: &mut Simulator, limit: usize) -> StopReason {
+        for _ in 0..limit {
+            match self.step(sim) {
+                StopReason::Stepped => {}
+                reason => return reason,
+            }
+            if let Some(breakpoint) = self.breakpoint_at_ip(sim) {
+                return StopReason::Breakpoint(breakpoint);
+            }
+        }
+        StopReason::Limit
+    }
+
+    /// The breakpoint matching the selected organism's next instruction
+    pub fn breakpoint_at_ip(&self, sim: &Simulator) -> Option<Breakpoint> {
+        let organism = sim.organisms.iter().find(|o| Some(o.id) == self.organism && o.alive)?;
+        let inst = sim.memory.read(organism.ip);
+        self.breakpoints.iter().copied().find(|b| b.hits(organism.ip, inst))
+    }
+
+    /// Disassembly of the selected organism's genome as (address, instruction)
+    pub fn disassemble(&self, sim: &Simulator) -> Vec<(usize, Instruction)> {
+        let Some(organism) = sim.organisms.iter().find(|o| Some(o.id) == self.organism) else {
+            return Vec::new();
+        };
+        (0..organism.size)
+            .map(|i| {
+                let addr = sim.memory.normalize_addr(organism.address + i);
+                (addr, sim.memory.read(addr))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::SimulationConfig;
+
+    fn ancestor_sim() -> Simulator {
+        let mut sim = Simulator::new(SimulationConfig {
+            memory_size: 4096,
+            mutation_rate: 0.0,
+            seed: Some(1),
+            ..Default::default()
+        }).unwrap();
+        sim.initialize_with_ancestor();
+        sim
+    }
+
+    #[test]
+    fn test_step_and_breakpoints() {
+        let mut sim = ancestor_sim();
+        let start = sim.organisms[0].address;
+        let mut debugger = Debugger::new();
+        debugger.organism = Some(sim.organisms[0].id);
+        assert_eq!(debugger.disassemble(&sim).len(), sim.organisms[0].size);
+
+        // The ancestor opens with its four-nop start template, then Zero and Not0 set CX to 1
+        for _ in 0..6 {
+            assert_eq!(debugger.step(&mut sim), StopReason::Stepped);
+        }
+        assert_eq!(sim.organisms[0].cx, 1);
+        assert_eq!(sim.organisms[0].ip, start + 6);
+
+        debugger.toggle_breakpoint(Breakpoint::Instruction(Instruction::MallocA));
+        let reason = debugger.run_until_divide(&mut sim, 10_000);
+        assert_eq!(reason, StopReason::Breakpoint(Breakpoint::Instruction(Instruction::MallocA)));
+        assert_eq!(sim.memory.read(sim.organisms[0].ip), Instruction::MallocA);
+
+        // The address breakpoint lies before the copy loop, so the run reaches Divide
+        debugger.toggle_breakpoint(Breakpoint::Instruction(Instruction::MallocA));
+        debugger.toggle_breakpoint(Breakpoint::Address(start + 2));
+        assert_eq!(debugger.breakpoints, vec![Breakpoint::Address(start + 2)]);
+        assert_eq!(debugger.run_until_divide(&mut sim, 10_000), StopReason::Divided);
+        assert_eq!(sim.organisms.len(), 2);
+        assert_eq!(sim.memory.get_slice(sim.organisms[1].address, sim.organisms[1].size),
+            sim.memory.get_slice(start, sim.organisms[0].size));
+    }
+
+    #[test]
+    fn test_missing_organism_is_dead() {
+        let mut sim = ancestor_sim();
+        let mut debugger = Debugger::new();
+        assert_eq!(debugger.step(&mut sim), StopReason::Dead);
+        debugger.organism = Some(99);
+        assert_eq!(debugger.run_until_divide(&mut sim, 10), StopReason::Dead);
+        assert!(debugger.disassemble(&sim).is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+use crate::genebank::{Genebank, GenotypeId};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+
+/// Ecological measures of the living population over genotypes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Diversity {
+    /// Number of living genotypes
+    pub richness: usize,
+    /// Number of distinct genome sizes among living genotypes
+    pub size_classes: usize,
+    /// Shannon index, -sum(p ln p) over genotype frequencies
+    pub shannon: f64,
+    /// Gini-Simpson index, 1 - sum(p^2): the chance two random organisms differ in genotype
+    pub simpson: f64,
+    /// Fraction of the population belonging to the most abundant genotype
+    pub dominant_share: f64,
+    /// Genotypes alive now but not at the previous measurement
+    pub appeared: usize,
+    /// Genotypes alive at the previous measurement but not now
+    pub extinct: usize,
+    /// (appeared + extinct) / (previous richness + richness), from 0 (no change) to 1
+    pub turnover: f64,
+}
+
+/// Measures diversity, remembering the living genotypes between calls so
+/// turnover covers the interval since the last measurement
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiversityTracker {
+    previous: BTreeSet<GenotypeId>,
+    /// Instruction count of the last measurement
+    pub last_measured: u64,
+}
+
+impl DiversityTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn measure(&mut self, genebank: &Genebank, time: u64) -> Diversity {
+        let living: BTreeSet<GenotypeId> = genebank.living_ids().collect();
+        let abundances: Vec<usize> = living.iter().map(|&id| genebank.get(id).unwrap().abundance).collect();
+        let total: usize = abundances.iter().sum();
+
+        let mut diversity = Diversity {
+            richness: living.len(),
+            size_classes: living
+                .iter()
+                .map(|&id| genebank.get(id).unwrap().size())
+                .collect::<HashSet<_>>()
+                .len(),
+            appeared: living.difference(&self.previous).count(),
+            extinct: self.previous.difference(&living).count(),
+            ..Default::default()
+        };
+
+        if total > 0 {
+            for &abundance in &abundances {
+                let p = abundance as f64 / total as f64;
+                diversity.shannon -= p * p.ln();
+                diversity.simpson += p * p;
+            }
+            diversity.simpson = 1.0 - diversity.simpson;
+            diversity.dominant_share = *abundances.iter().max().unwrap() as f64 / total as f64;
+        }
+        let compared = self.previous.len() + living.len();
+        if compared > 0 {
+            diversity.turnover = (diversity.appeared + diversity.extinct) as f64 / compared as f64;
+        }
+
+        self.previous = living;
+        self.last_measured = time;
+        diversity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::Instruction;
+
+    #[test]
+    fn test_indices_and_turnover() {
+        let a = vec![Instruction::Nop0; 4];
+        let b = vec![Instruction::Nop1; 4];
+        let c = vec![Instruction::Nop1; 6];
+        let mut genebank = Genebank::new();
+        let mut tracker = DiversityTracker::new();
+        assert_eq!(tracker.measure(&genebank, 0), Diversity::default());
+
+        // Two equally common genotypes
+        let ga = genebank.record_birth(&a, None, 0);
+        genebank.record_birth(&a, None, 0);
+        genebank.record_birth(&b, None, 0);
+        genebank.record_birth(&b, None, 0);
+        let d = tracker.measure(&genebank, 100);
+        assert_eq!((d.richness, d.size_classes, d.appeared, d.extinct), (2, 1, 2, 0));
+        assert!((d.shannon - 2f64.ln()).abs() < 1e-12);
+        assert!((d.simpson - 0.5).abs() < 1e-12);
+        assert_eq!(d.dominant_share, 0.5);
+        assert_eq!(d.turnover, 1.0);
+
+        // `a` dies out and `c` appears
+        genebank.record_death(ga);
+        genebank.record_death(ga);
+        genebank.record_birth(&c, None, 0);
+        let d = tracker.measure(&genebank, 200);
+        assert_eq!((d.richness, d.size_classes, d.appeared, d.extinct), (2, 2, 1, 1));
+        assert!((d.dominant_share - 2.0 / 3.0).abs() < 1e-12);
+        assert_eq!(d.turnover, 0.5);
+        assert_eq!(tracker.last_measured, 200);
+    }
+}
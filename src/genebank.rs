@@ -0,0 +1,283 @@
+use crate::instruction::Instruction;
+use crate::isa::Isa;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Index of a genotype within the genebank
+pub type GenotypeId = usize;
+
+/// A distinct genome that has appeared in the soup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Genotype {
+    /// Tierra-style name: zero-padded size followed by a label, e.g. `0080aaa`
+    pub name: String,
+
+    /// Stable 64-bit FNV-1a hash of the genome
+    pub hash: u64,
+
+    /// Genome as instruction codes (`Instruction::to_u8`), which do not
+    /// depend on the instruction set
+    pub genome: Vec<u8>,
+
+    /// Instruction count at which this genotype was first born
+    pub first_seen: u64,
+
+    /// Genotype of the first organism's parent
+    pub parent: Option<GenotypeId>,
+
+    /// Number of living organisms with this genotype
+    pub abundance: usize,
+
+    /// Highest abundance ever reached
+    pub peak_abundance: usize,
+
+    /// Total organisms ever born with this genotype
+    pub births: u64,
+}
+
+impl Genotype {
+    /// Genome length in instructions
+    pub fn size(&self) -> usize {
+        self.genome.len()
+    }
+
+    /// Decode the genome back into instructions. A code no instruction has
+    /// means the genome came from an incompatible genebank.
+    pub fn instructions(&self) -> Result<Vec<Instruction>, String> {
+        self.genome
+            .iter()
+            .map(|&byte| Instruction::from_u8(byte).ok_or_else(|| format!("Genotype {} has unknown instruction code {}", self.name, byte)))
+            .collect()
+    }
+
+    /// Has at least one living member
+    pub fn is_alive(&self) -> bool {
+        self.abundance > 0
+    }
+}
+
+/// Archive and census of every genotype seen during a run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Genebank {
+    /// Instruction set the genomes were written in
+    #[serde(default)]
+    pub isa: Isa,
+
+    genotypes: Vec<Genotype>,
+
+    /// Genome hash -> genotypes with that hash (more than one only on collision)
+    by_hash: HashMap<u64, Vec<GenotypeId>>,
+
+    /// Next label to hand out for each size class
+    next_label: HashMap<usize, usize>,
+}
+
+impl Genebank {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the birth of an organism with the given genome and return its genotype.
+    /// New genomes are archived under a fresh name.
+    pub fn record_birth(&mut self, genome: &[Instruction], parent: Option<GenotypeId>, time: u64) -> GenotypeId {
+        let bytes: Vec<u8> = genome.iter().map(|inst| inst.to_u8()).collect();
+        let hash = genome_hash(&bytes);
+
+        let id = match self.lookup(hash, &bytes) {
+            Some(id) => id,
+            None => {
+                let id = self.genotypes.len();
+                let name = self.next_name(bytes.len());
+                self.genotypes.push(Genotype {
+                    name,
+                    hash,
+                    genome: bytes,
+                    first_seen: time,
+                    parent,
+                    abundance: 0,
+                    peak_abundance: 0,
+                    births: 0,
+                });
+                self.by_hash.entry(hash).or_default().push(id);
+                id
+            }
+        };
+
+        let genotype = &mut self.genotypes[id];
+        genotype.abundance += 1;
+        genotype.births += 1;
+        genotype.peak_abundance = genotype.peak_abundance.max(genotype.abundance);
+        id
+    }
+
+    /// Record the death of an organism of the given genotype
+    pub fn record_death(&mut self, id: GenotypeId) {
+        if let Some(genotype) = self.genotypes.get_mut(id) {
+            genotype.abundance = genotype.abundance.saturating_sub(1);
+        }
+    }
+
+    /// Get a genotype by id
+    pub fn get(&self, id: GenotypeId) -> Option<&Genotype> {
+        self.genotypes.get(id)
+    }
+
+    /// Find a genotype by its name
+    pub fn find_by_name(&self, name: &str) -> Option<&Genotype> {
+        self.genotypes.iter().find(|g| g.name == name)
+    }
+
+    /// Find the genotype of an exact genome, if it has been seen
+    pub fn find_by_genome(&self, genome: &[Instruction]) -> Option<&Genotype> {
+        let bytes: Vec<u8> = genome.iter().map(|inst| inst.to_u8()).collect();
+        self.lookup(genome_hash(&bytes), &bytes).map(|id| &self.genotypes[id])
+    }
+
+    /// All genotypes ever seen, in order of first appearance
+    pub fn iter(&self) -> impl Iterator<Item = &Genotype> {
+        self.genotypes.iter()
+    }
+
+    /// Genotypes with at least one living member
+    pub fn living(&self) -> impl Iterator<Item = &Genotype> {
+        self.genotypes.iter().filter(|g| g.is_alive())
+    }
+
+    /// IDs of the genotypes with at least one living member
+    pub fn living_ids(&self) -> impl Iterator<Item = GenotypeId> + '_ {
+        (0..self.genotypes.len()).filter(|&id| self.genotypes[id].is_alive())
+    }
+
+    /// The `n` most abundant living genotypes, most abundant first
+    pub fn most_abundant(&self, n: usize) -> Vec<&Genotype> {
+        let mut living: Vec<_> = self.living().collect();
+        living.sort_by(|a, b| b.abundance.cmp(&a.abundance).then_with(|| a.name.cmp(&b.name)));
+        living.truncate(n);
+        living
+    }
+
+    /// Number of genotypes ever seen
+    pub fn len(&self) -> usize {
+        self.genotypes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.genotypes.is_empty()
+    }
+
+    /// Number of genotypes currently alive
+    pub fn living_count(&self) -> usize {
+        self.living().count()
+    }
+
+    /// Write the genebank to a JSON file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        let text = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize genebank: {}", e))?;
+        fs::write(path, text).map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+    }
+
+    /// Read a genebank from a JSON file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        let bank: Self = serde_json::from_str(&text).map_err(|e| format!("Invalid genebank {}: {}", path.display(), e))?;
+        for genotype in &bank.genotypes {
+            genotype.instructions().map_err(|e| format!("Invalid genebank {}: {}", path.display(), e))?;
+        }
+        Ok(bank)
+    }
+
+    fn lookup(&self, hash: u64, bytes: &[u8]) -> Option<GenotypeId> {
+        self.by_hash
+            .get(&hash)?
+            .iter()
+            .copied()
+            .find(|&id| self.genotypes[id].genome == bytes)
+    }
+
+    fn next_name(&mut self, size: usize) -> String {
+        let counter = self.next_label.entry(size).or_insert(0);
+        let label = label_for(*counter);
+        *counter += 1;
+        format!("{:04}{}", size, label)
+    }
+}
+
+/// FNV-1a hash; stable across runs and platforms, unlike `DefaultHasher`
+pub fn genome_hash(bytes: &[u8]) -> u64 {
+    const OFFSET: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    bytes.iter().fold(OFFSET, |hash, &byte| (hash ^ byte as u64).wrapping_mul(PRIME))
+}
+
+/// Label sequence within a size class: aaa, aab, ..., zzz, baaaa, ...
+fn label_for(mut index: usize) -> String {
+    let mut letters = Vec::new();
+    while letters.len() < 3 || index > 0 {
+        letters.push(b'a' + (index % 26) as u8);
+        index /= 26;
+    }
+    letters.reverse();
+    String::from_utf8(letters).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Instruction::*;
+
+    #[test]
+    fn test_genotype_naming_and_abundance() {
+        let mut bank = Genebank::new();
+        let ancestor = vec![Nop1; 80];
+        let mut variant = ancestor.clone();
+        variant[10] = IncA;
+
+        let a = bank.record_birth(&ancestor, None, 0);
+        let a2 = bank.record_birth(&ancestor, Some(a), 50);
+        let v = bank.record_birth(&variant, Some(a), 100);
+        let short = bank.record_birth(&ancestor[..45], Some(a), 120);
+
+        assert_eq!(a, a2);
+        assert_eq!(bank.get(a).unwrap().name, "0080aaa");
+        assert_eq!(bank.get(v).unwrap().name, "0080aab");
+        assert_eq!(bank.get(short).unwrap().name, "0045aaa");
+        assert_eq!(bank.get(v).unwrap().parent, Some(a));
+        assert_eq!(bank.get(v).unwrap().first_seen, 100);
+        assert_eq!(bank.find_by_genome(&variant).unwrap().name, "0080aab");
+        assert_eq!(bank.get(v).unwrap().instructions(), Ok(variant.clone()));
+
+        bank.record_death(a);
+        bank.record_death(a);
+        let ancestor_genotype = bank.find_by_name("0080aaa").unwrap();
+        assert_eq!(ancestor_genotype.abundance, 0);
+        assert_eq!(ancestor_genotype.peak_abundance, 2);
+        assert_eq!(ancestor_genotype.births, 2);
+        assert_eq!(bank.living_count(), 2);
+    }
+
+    #[test]
+    fn test_unknown_instruction_codes_are_rejected() {
+        let mut bank = Genebank::new();
+        let id = bank.record_birth(&[Nop0, Divide], None, 0);
+        bank.genotypes[id].genome[1] = Instruction::COUNT as u8;
+        assert!(bank.get(id).unwrap().instructions().unwrap_err().contains("unknown instruction code"));
+
+        let path = std::env::temp_dir().join(format!("tierra-genebank-{}.json", std::process::id()));
+        bank.save(&path).unwrap();
+        assert!(Genebank::load(&path).unwrap_err().contains("unknown instruction code"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_labels_roll_over() {
+        assert_eq!(label_for(0), "aaa");
+        assert_eq!(label_for(25), "aaz");
+        assert_eq!(label_for(26), "aba");
+        assert_eq!(label_for(26 * 26 * 26), "baaa");
+    }
+}
@@ -0,0 +1,632 @@
+use crate::assembler;
+use crate::batch::{Batch, RunResult, SeedGenome};
+use crate::instruction::Instruction;
+use crate::metrics::{self, Metric, MetricsSink};
+use crate::simulator::{SimulationConfig, Simulator};
+use crate::snapshot;
+use std::fs;
+use std::time::Duration;
+
+/// Time between progress lines of a batch
+const BATCH_REPORT_EVERY: Duration = Duration::from_secs(5);
+
+/// Exit code for a run that completed normally
+pub const EXIT_OK: i32 = 0;
+/// Exit code for a run whose population died out before the end, or a
+/// batch whose every run did
+pub const EXIT_EXTINCT: i32 = 1;
+/// Exit code for bad command-line arguments or an unreadable config file
+pub const EXIT_USAGE: i32 = 2;
+
+pub const USAGE: &str = "\
+Usage: tierra-headless [OPTIONS]
+
+Options:
+  --config <FILE>          Load a JSON SimulationConfig (missing fields use defaults)
+  --seed <N>               RNG seed for a reproducible run
+  --memory-size <N>        Soup size in instructions
+  --topology <T>           linear, circular (default), torus[:WIDTH[,RADIUS]] or
+                           islands[:COUNT[,MIGRATION_RATE]]
+  --mutation-rate <F>      Copy error probability per MovII/MovCD write
+  --cosmic-ray-rate <F>    Probability per executed instruction of flipping a random cell
+  --flaw-rate <F>          Probability that an arithmetic instruction is off by one
+  --insertion-rate <F>     Probability per divide of inserting an instruction
+  --deletion-rate <F>      Probability per divide of deleting an instruction
+  --crossover-rate <F>     Probability per divide of crossover with another organism
+  --reaper-threshold <F>   Memory usage fraction above which the reaper kills organisms
+  --max-population <N>     Population cap
+  --time-slice <N>         Instructions per organism per turn
+  --slice-policy <P>       fixed, size[:POWER] or jitter[:FRACTION]
+  --placement <P>          Daughter placement: first, best, random (default) or near
+  --divide-min-written <F> Fraction of the daughter block written before Divide succeeds
+  --compaction <BOOL>      Compact the soup when fragmentation blocks a MallocA
+  --permissions <SPEC>     Memory permissions, e.g. own=rwx,daughter=rwx,foreign=r-x
+  --isa <NAME>             Instruction set: tierra-rs (default) or tierra0
+  --steps <N>              Stop after N scheduler steps
+  --instructions <N>       Stop after N executed instructions
+  --report-every <N>       Print a statistics summary every N steps (0 disables)
+  --genome <SPEC>          Seed the soup from a .tie genome instead of the built-in ancestor
+                           (repeatable). SPEC is FILE[*COUNT][@ADDRESS]
+  --inoculate <N:SPEC>     Inject SPEC into the running soup once N instructions
+                           have executed (repeatable)
+  --load-snapshot <FILE>   Resume from a snapshot instead of seeding the ancestor
+  --save-snapshot <FILE>   Write a snapshot when the run ends
+  --snapshot-every <N>     Also overwrite the snapshot file every N steps
+  --save-genebank <FILE>   Write the genotype archive when the run ends
+  --metrics <FILE>         Stream a metrics time series (JSON Lines if FILE ends in
+                           .jsonl, CSV otherwise)
+  --metrics-every <N>      Instructions between metrics samples (default 100000)
+  --metrics-fields <LIST>  Comma-separated metrics to record (default: all of
+                           instructions,population,mean_size,median_size,genotypes,
+                           births,deaths,mutations,memory_used,size_classes,shannon,
+                           simpson,new_genotypes,extinct_genotypes,turnover,
+                           dominant_share,largest_free_block,free_blocks)
+  --save-lineage <FILE>    Write the lineage tree when the run ends (GraphML if FILE ends
+                           in .graphml, Newick otherwise)
+  --runs <N>               Run N replicate soups in parallel with seeds SEED..SEED+N-1,
+                           where SEED is --seed (default 1), and summarize them
+  --threads <N>            Worker threads for --runs (default: one per core)
+  --out-dir <DIR>          With --runs, write summary.json, runs.csv and a run-NNN
+                           directory per run (metrics.csv, genebank.json, lineage.nwk,
+                           result.json)
+  -h, --help               Print this help";
+
+/// A genome file to place in the soup, written `FILE[*COUNT][@ADDRESS]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenomeSpec {
+    pub path: String,
+    pub count: usize,
+    pub address: Option<usize>,
+}
+
+impl GenomeSpec {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (rest, address) = match text.rsplit_once('@') {
+            Some((rest, addr)) => (rest, Some(parse_value("genome address", addr)?)),
+            None => (text, None),
+        };
+        let (path, count) = match rest.rsplit_once('*') {
+            Some((path, count)) => (path, parse_value("genome count", count)?),
+            None => (rest, 1),
+        };
+        if path.is_empty() {
+            return Err(format!("Missing genome file in '{}'", text));
+        }
+        Ok(Self {
+            path: path.to_string(),
+            count,
+            address,
+        })
+    }
+}
+
+/// A genome injected into the running soup at a given instruction count,
+/// written `INSTRUCTION:SPEC`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inoculation {
+    pub at_instruction: u64,
+    pub genome: GenomeSpec,
+}
+
+impl Inoculation {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (at, spec) = text
+            .split_once(':')
+            .ok_or_else(|| format!("Expected INSTRUCTION:SPEC, got '{}'", text))?;
+        Ok(Self {
+            at_instruction: parse_value("inoculation time", at)?,
+            genome: GenomeSpec::parse(spec)?,
+        })
+    }
+}
+
+/// Options for a headless (no display) simulation run
+#[derive(Debug, Clone)]
+pub struct HeadlessOptions {
+    pub config: SimulationConfig,
+    /// Maximum number of scheduler steps
+    pub steps: Option<u64>,
+    /// Maximum number of executed instructions
+    pub instructions: Option<u64>,
+    /// Steps between statistics reports
+    pub report_every: u64,
+    /// `.tie` genomes to seed instead of the built-in ancestor
+    pub genomes: Vec<GenomeSpec>,
+    /// Genomes injected later in the run, in order of instruction count
+    pub inoculations: Vec<Inoculation>,
+    /// Snapshot to resume from; its embedded config replaces `config`
+    pub load_snapshot: Option<String>,
+    /// Snapshot file written at the end of the run
+    pub save_snapshot: Option<String>,
+    /// Steps between periodic snapshots (0 disables)
+    pub snapshot_every: u64,
+    /// Genebank file written at the end of the run
+    pub save_genebank: Option<String>,
+    /// Lineage tree written at the end of the run (Newick, or GraphML for `.graphml`)
+    pub save_lineage: Option<String>,
+    /// Metrics time series file (CSV, or JSON Lines for `.jsonl`)
+    pub metrics: Option<String>,
+    /// Instructions between metrics samples
+    pub metrics_every: u64,
+    /// Metrics recorded in each sample
+    pub metrics_fields: Vec<Metric>,
+    /// Number of replicate runs; `None` is a single run
+    pub runs: Option<usize>,
+    /// Worker threads for replicate runs (0 is one per core)
+    pub threads: usize,
+    /// Directory for the files of replicate runs
+    pub out_dir: Option<String>,
+}
+
+impl Default for HeadlessOptions {
+    fn default() -> Self {
+        Self {
+            config: SimulationConfig::default(),
+            steps: None,
+            instructions: None,
+            report_every: 10_000,
+            genomes: Vec::new(),
+            inoculations: Vec::new(),
+            load_snapshot: None,
+            save_snapshot: None,
+            snapshot_every: 0,
+            save_genebank: None,
+            save_lineage: None,
+            metrics: None,
+            metrics_every: 100_000,
+            metrics_fields: Metric::ALL.to_vec(),
+            runs: None,
+            threads: 0,
+            out_dir: None,
+        }
+    }
+}
+
+impl HeadlessOptions {
+    /// Parse command-line arguments (without the program name).
+    /// Returns `Ok(None)` when help was requested.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Option<Self>, String> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+
+        // The config file is applied first so explicit flags override it
+        let mut pending: Vec<(String, String)> = Vec::new();
+        while let Some(flag) = args.next() {
+            if flag == "-h" || flag == "--help" {
+                return Ok(None);
+            }
+            let value = args
+                .next()
+                .ok_or_else(|| format!("Missing value for {}", flag))?;
+            if flag == "--config" {
+                options.config = load_config(&value)?;
+            } else {
+                pending.push((flag, value));
+            }
+        }
+
+        for (flag, value) in pending {
+            match flag.as_str() {
+                "--seed" => options.config.seed = Some(parse_value(&flag, &value)?),
+                "--memory-size" => options.config.memory_size = parse_value(&flag, &value)?,
+                "--topology" => options.config.topology = value.parse()?,
+                "--mutation-rate" => options.config.mutation_rate = parse_value(&flag, &value)?,
+                "--cosmic-ray-rate" => options.config.cosmic_ray_rate = parse_value(&flag, &value)?,
+                "--flaw-rate" => options.config.flaw_rate = parse_value(&flag, &value)?,
+                "--insertion-rate" => options.config.insertion_rate = parse_value(&flag, &value)?,
+                "--deletion-rate" => options.config.deletion_rate = parse_value(&flag, &value)?,
+                "--crossover-rate" => options.config.crossover_rate = parse_value(&flag, &value)?,
+                "--reaper-threshold" => options.config.reaper_threshold = parse_value(&flag, &value)?,
+                "--max-population" => options.config.max_population = parse_value(&flag, &value)?,
+                "--time-slice" => options.config.time_slice = parse_value(&flag, &value)?,
+                "--slice-policy" => options.config.slice_policy = value.parse()?,
+                "--placement" => options.config.placement = value.parse()?,
+                "--divide-min-written" => options.config.divide_min_written = parse_value(&flag, &value)?,
+                "--compaction" => options.config.compaction = parse_value(&flag, &value)?,
+                "--permissions" => options.config.permissions = value.parse()?,
+                "--isa" => options.config.isa = value.parse()?,
+                "--steps" => options.steps = Some(parse_value(&flag, &value)?),
+                "--instructions" => options.instructions = Some(parse_value(&flag, &value)?),
+                "--report-every" => options.report_every = parse_value(&flag, &value)?,
+                "--genome" => options.genomes.push(GenomeSpec::parse(&value)?),
+                "--inoculate" => options.inoculations.push(Inoculation::parse(&value)?),
+                "--load-snapshot" => options.load_snapshot = Some(value),
+                "--save-snapshot" => options.save_snapshot = Some(value),
+                "--snapshot-every" => options.snapshot_every = parse_value(&flag, &value)?,
+                "--save-genebank" => options.save_genebank = Some(value),
+                "--save-lineage" => options.save_lineage = Some(value),
+                "--metrics" => options.metrics = Some(value),
+                "--metrics-every" => options.metrics_every = parse_value(&flag, &value)?,
+                "--metrics-fields" => options.metrics_fields = metrics::parse_fields(&value)?,
+                "--runs" => options.runs = Some(parse_value(&flag, &value)?),
+                "--threads" => options.threads = parse_value(&flag, &value)?,
+                "--out-dir" => options.out_dir = Some(value),
+                _ => return Err(format!("Unknown option: {}", flag)),
+            }
+        }
+
+        if options.steps.is_none() && options.instructions.is_none() {
+            return Err("One of --steps or --instructions is required".to_string());
+        }
+        if options.snapshot_every > 0 && options.save_snapshot.is_none() {
+            return Err("--snapshot-every requires --save-snapshot".to_string());
+        }
+        if options.config.memory_size == 0 {
+            return Err("--memory-size must be positive".to_string());
+        }
+        options.config.validate()?;
+        if options.runs.is_some() {
+            let single_run_only = [
+                ("--inoculate", !options.inoculations.is_empty()),
+                ("--load-snapshot", options.load_snapshot.is_some()),
+                ("--save-snapshot", options.save_snapshot.is_some()),
+                ("--save-genebank", options.save_genebank.is_some()),
+                ("--save-lineage", options.save_lineage.is_some()),
+                ("--metrics", options.metrics.is_some()),
+            ];
+            if let Some((flag, _)) = single_run_only.iter().find(|(_, set)| *set) {
+                return Err(format!("{} cannot be combined with --runs; use --out-dir for per-run files", flag));
+            }
+        } else if options.out_dir.is_some() {
+            return Err("--out-dir requires --runs".to_string());
+        }
+        options.inoculations.sort_by_key(|inoculation| inoculation.at_instruction);
+
+        Ok(Some(options))
+    }
+}
+
+/// Run a simulation to completion, printing periodic summaries.
+/// Returns the process exit code.
+pub fn run(options: &HeadlessOptions) -> i32 {
+    if let Some(runs) = options.runs {
+        return run_batch(options, runs);
+    }
+    // Assemble every genome up front so a typo fails before hours of simulation
+    let specs: Vec<&GenomeSpec> = options.genomes.iter().chain(options.inoculations.iter().map(|i| &i.genome)).collect();
+    let mut assemblies = Vec::new();
+    for spec in &specs {
+        match assembler::load_assembly(&spec.path) {
+            Ok(assembly) => assemblies.push(assembly),
+            Err(message) => {
+                eprintln!("{}", message);
+                return EXIT_USAGE;
+            }
+        }
+    }
+
+    let mut sim = match &options.load_snapshot {
+        Some(path) => match snapshot::load(path) {
+            Ok(sim) => {
+                println!("resumed from {} at inst={}", path, sim.stats.total_instructions);
+                sim
+            }
+            Err(message) => {
+                eprintln!("{}", message);
+                return EXIT_USAGE;
+            }
+        },
+        None => match Simulator::new(options.config.clone()) {
+            Ok(sim) => sim,
+            Err(message) => {
+                eprintln!("{}", message);
+                return EXIT_USAGE;
+            }
+        },
+    };
+
+    let mut genomes = Vec::new();
+    for (spec, assembly) in specs.iter().zip(&assemblies) {
+        match assembly.genome_for(sim.cpu.isa) {
+            Ok(genome) => genomes.push(genome.to_vec()),
+            Err(message) => {
+                eprintln!("{}: {}", spec.path, message);
+                return EXIT_USAGE;
+            }
+        }
+    }
+    let (seed_genomes, inoculation_genomes) = genomes.split_at(options.genomes.len());
+
+    if options.load_snapshot.is_none() {
+        if options.genomes.is_empty() {
+            sim.initialize_with_ancestor();
+        }
+        for (spec, genome) in options.genomes.iter().zip(seed_genomes) {
+            if inoculate(&mut sim, spec, genome) < spec.count {
+                return EXIT_USAGE;
+            }
+        }
+    }
+
+    println!("config: {}", serde_json::to_string(&sim.config).unwrap_or_default());
+
+    let mut metrics_sink = match &options.metrics {
+        Some(path) => match MetricsSink::create(path, options.metrics_fields.clone(), options.metrics_every) {
+            Ok(sink) => Some(sink),
+            Err(message) => {
+                eprintln!("{}", message);
+                return EXIT_USAGE;
+            }
+        },
+        None => None,
+    };
+
+    let mut pending = options.inoculations.iter().zip(inoculation_genomes).peekable();
+    let mut steps: u64 = 0;
+    loop {
+        while let Some((inoculation, genome)) =
+            pending.next_if(|(i, _)| i.at_instruction <= sim.stats.total_instructions)
+        {
+            inoculate(&mut sim, &inoculation.genome, genome);
+        }
+
+        if options.steps.is_some_and(|limit| steps >= limit)
+            || options.instructions.is_some_and(|limit| sim.stats.total_instructions >= limit)
+        {
+            break;
+        }
+
+        sim.step();
+        steps += 1;
+
+        if let Some(sink) = &mut metrics_sink {
+            if let Err(e) = sink.maybe_sample(&sim) {
+                eprintln!("Failed to write metrics: {}", e);
+                metrics_sink = None;
+            }
+        }
+
+        if options.report_every > 0 && steps.is_multiple_of(options.report_every) {
+            println!("step={} {}", steps, sim.stats.summary());
+        }
+
+        if options.snapshot_every > 0 && steps.is_multiple_of(options.snapshot_every) {
+            save_snapshot(&sim, options);
+        }
+
+        if sim.organisms.iter().all(|o| !o.alive) {
+            println!("step={} {}", steps, sim.stats.summary());
+            eprintln!("Population went extinct after {} steps", steps);
+            finish_metrics(metrics_sink, &sim);
+            return EXIT_EXTINCT;
+        }
+    }
+
+    println!("final step={} {}", steps, sim.stats.summary());
+    for genotype in sim.genebank.most_abundant(5) {
+        println!("genotype {} abundance={} peak={}", genotype.name, genotype.abundance, genotype.peak_abundance);
+    }
+    println!("roles: {}", sim.interactions.summary(&sim.genebank));
+    for (user, provider, count) in sim.interactions.strongest(5) {
+        let name = |id| sim.genebank.get(id).map_or("?", |g| g.name.as_str());
+        println!("interaction {} uses {} count={}", name(user), name(provider), count);
+    }
+    let total = sim.cpu.profile.total();
+    println!("profile: errors={} searches={} failed_searches={}", total.errors, total.searches, total.failed_searches);
+    for (inst, counters) in sim.cpu.profile.busiest().into_iter().take(5) {
+        let distance = counters.average_search_distance().map_or("-".to_string(), |d| format!("{:.1}", d));
+        println!(
+            "opcode {} executions={} errors={} searches={} failed_searches={} avg_distance={}",
+            sim.cpu.isa.mnemonic(inst), counters.executions, counters.errors,
+            counters.searches, counters.failed_searches, distance
+        );
+    }
+    save_snapshot(&sim, options);
+    finish_metrics(metrics_sink, &sim);
+    if let Some(path) = &options.save_genebank {
+        if let Err(message) = sim.genebank.save(path) {
+            eprintln!("{}", message);
+        }
+    }
+    if let Some(path) = &options.save_lineage {
+        if let Err(message) = sim.lineage.save(&sim.genebank, path) {
+            eprintln!("{}", message);
+        }
+    }
+    EXIT_OK
+}
+
+/// Run `runs` replicate soups in parallel, printing progress while they run
+/// and a summary at the end. Returns the process exit code.
+fn run_batch(options: &HeadlessOptions, runs: usize) -> i32 {
+    let mut genomes = Vec::new();
+    for spec in &options.genomes {
+        let genome = assembler::load_assembly(&spec.path).and_then(|assembly| {
+            assembly
+                .genome_for(options.config.isa)
+                .map(|genome| genome.to_vec())
+                .map_err(|message| format!("{}: {}", spec.path, message))
+        });
+        match genome {
+            Ok(genome) => genomes.push(SeedGenome { genome, count: spec.count, address: spec.address }),
+            Err(message) => {
+                eprintln!("{}", message);
+                return EXIT_USAGE;
+            }
+        }
+    }
+
+    let first_seed = options.config.seed.unwrap_or(1);
+    let Some(seeds) = (0..runs as u64).map(|i| first_seed.checked_add(i)).collect() else {
+        eprintln!("--seed {} leaves no room for {} consecutive run seeds", first_seed, runs);
+        return EXIT_USAGE;
+    };
+    let batch = Batch {
+        config: options.config.clone(),
+        seeds,
+        steps: options.steps,
+        instructions: options.instructions,
+        genomes,
+        threads: options.threads,
+        out_dir: options.out_dir.as_ref().map(Into::into),
+        metrics_every: options.metrics_every,
+        metrics_fields: options.metrics_fields.clone(),
+    };
+    println!("config: {}", serde_json::to_string(&batch.config).unwrap_or_default());
+    let summary = match batch.run(BATCH_REPORT_EVERY, |progress| println!("progress {}", progress)) {
+        Ok(summary) => summary,
+        Err(error) => {
+            for run in error.runs.iter().flatten() {
+                print_run(run);
+            }
+            eprintln!("{}", error);
+            return EXIT_USAGE;
+        }
+    };
+
+    for run in &summary.runs {
+        print_run(run);
+    }
+    println!("extinctions={}/{}", summary.extinctions, summary.runs.len());
+    for aggregate in &summary.aggregates {
+        println!(
+            "summary {} mean={:.4} sd={:.4} min={:.4} median={:.4} max={:.4}",
+            aggregate.metric, aggregate.mean, aggregate.sd, aggregate.min, aggregate.median, aggregate.max
+        );
+    }
+    if runs > 0 && summary.extinctions == runs {
+        EXIT_EXTINCT
+    } else {
+        EXIT_OK
+    }
+}
+
+/// Print one finished batch run
+fn print_run(run: &RunResult) {
+    println!(
+        "run {} seed={} steps={} inst={}{}",
+        run.index, run.seed, run.steps, run.instructions, if run.extinct { " extinct" } else { "" }
+    );
+}
+
+/// Write the last metrics sample and flush the file
+fn finish_metrics(sink: Option<MetricsSink<std::io::BufWriter<fs::File>>>, sim: &Simulator) {
+    if let Some(mut sink) = sink {
+        if let Err(e) = sink.finish(sim) {
+            eprintln!("Failed to write metrics: {}", e);
+        }
+    }
+}
+
+/// Place a genome spec in the soup, reporting how many copies fit
+fn inoculate(sim: &mut Simulator, spec: &GenomeSpec, genome: &[Instruction]) -> usize {
+    let placed = sim.inoculate(genome, spec.address, spec.count).len();
+    println!(
+        "inoculated {}/{} of {} at inst={}",
+        placed, spec.count, spec.path, sim.stats.total_instructions
+    );
+    if placed < spec.count {
+        eprintln!("No room in the soup for all copies of {}", spec.path);
+    }
+    placed
+}
+
+/// Write the configured snapshot file, reporting (but surviving) I/O errors
+fn save_snapshot(sim: &Simulator, options: &HeadlessOptions) {
+    if let Some(path) = &options.save_snapshot {
+        if let Err(message) = snapshot::save(sim, path) {
+            eprintln!("{}", message);
+        }
+    }
+}
+
+fn load_config(path: &str) -> Result<SimulationConfig, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    serde_json::from_str(&text).map_err(|e| format!("Invalid config {}: {}", path, e))
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}", flag, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::SlicePolicy;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_overrides_and_requires_limit() {
+        let options = HeadlessOptions::parse(args(&["--seed", "7", "--steps", "100", "--time-slice", "10", "--slice-policy", "size:0.5", "--isa", "tierra0", "--placement", "best", "--compaction", "true"]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(options.config.seed, Some(7));
+        assert_eq!(options.config.time_slice, 10);
+        assert_eq!(options.config.slice_policy, SlicePolicy::SizePower { power: 0.5 });
+        assert_eq!(options.config.isa, crate::isa::Isa::Tierra0);
+        assert_eq!(options.config.placement, crate::allocator::Placement::BestFit);
+        assert!(options.config.compaction);
+        assert_eq!(options.steps, Some(100));
+        assert_eq!(options.metrics_fields, Metric::ALL.to_vec());
+
+        let options = HeadlessOptions::parse(args(&["--steps", "1", "--metrics-fields", "population, deaths"]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(options.metrics_fields, vec![Metric::Population, Metric::Deaths]);
+
+        let options = HeadlessOptions::parse(args(&["--steps", "1", "--memory-size", "1024", "--topology", "torus:32,4"]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(options.config.topology, crate::topology::Topology::Torus { width: 32, radius: 4 });
+        assert!(HeadlessOptions::parse(args(&["--steps", "1", "--memory-size", "1024", "--topology", "torus:2048"])).is_err());
+
+        let options = HeadlessOptions::parse(args(&["--steps", "1", "--divide-min-written", "0.5"])).unwrap().unwrap();
+        assert_eq!(options.config.divide_min_written, 0.5);
+        for bad in ["NaN", "inf", "1.5", "-0.1"] {
+            let error = HeadlessOptions::parse(args(&["--steps", "1", "--divide-min-written", bad])).unwrap_err();
+            assert!(error.starts_with("Divide written fraction must be between 0 and 1"), "{}", error);
+        }
+
+        let options = HeadlessOptions::parse(args(&["--steps", "1", "--runs", "50", "--threads", "8", "--out-dir", "out"]))
+            .unwrap()
+            .unwrap();
+        assert_eq!((options.runs, options.threads, options.out_dir.as_deref()), (Some(50), 8, Some("out")));
+        assert!(HeadlessOptions::parse(args(&["--steps", "1", "--runs", "2", "--metrics", "m.csv"])).is_err());
+        assert!(HeadlessOptions::parse(args(&["--steps", "1", "--out-dir", "out"])).is_err());
+        assert!(HeadlessOptions::parse(args(&["--steps", "1", "--metrics-fields", "iq"])).is_err());
+
+        assert!(HeadlessOptions::parse(args(&["--seed", "7"])).is_err());
+        assert!(HeadlessOptions::parse(args(&["--steps"])).is_err());
+        assert!(HeadlessOptions::parse(args(&["--help"])).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_genome_specs() {
+        let options = HeadlessOptions::parse(args(&[
+            "--steps", "1",
+            "--genome", "a.tie*3@100",
+            "--genome", "b.tie",
+            "--inoculate", "5000:p.tie@7",
+            "--inoculate", "10:q.tie*2",
+        ]))
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(options.genomes[0], GenomeSpec { path: "a.tie".into(), count: 3, address: Some(100) });
+        assert_eq!(options.genomes[1], GenomeSpec { path: "b.tie".into(), count: 1, address: None });
+        assert_eq!(options.inoculations[0].at_instruction, 10);
+        assert_eq!(options.inoculations[0].genome.count, 2);
+        assert_eq!(options.inoculations[1].genome.address, Some(7));
+        assert!(Inoculation::parse("p.tie").is_err());
+    }
+
+    #[test]
+    fn test_run_stops_at_instruction_limit() {
+        let mut options = HeadlessOptions::default();
+        options.config.seed = Some(3);
+        options.config.max_population = 10;
+        options.instructions = Some(5_000);
+        options.report_every = 0;
+        assert_eq!(run(&options), EXIT_OK);
+
+        // Replicate seeds count up from --seed and must not overflow
+        options.config.seed = Some(u64::MAX);
+        options.runs = Some(2);
+        assert_eq!(run(&options), EXIT_USAGE);
+    }
+}
@@ -1,8 +1,12 @@
-/// Tierra instruction set - simplified assembly-like operations
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use serde::{Deserialize, Serialize};
+
+/// Every operation the CPU can execute. Which of them a simulation uses,
+/// and under which opcodes, is decided by its instruction set (`isa.rs`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum Instruction {
     // Template matching and addressing
+    #[default]
     Nop0 = 0,      // No operation, also used for templates
     Nop1 = 1,      // No operation, also used for templates
 
@@ -42,41 +46,44 @@ pub enum Instruction {
 
     // Control
     Halt = 26,     // Kill the organism
+
+    // Replication
+    MovII = 27,    // Copy the instruction at [AX] to [BX]
+
+    // Operations of Tierra's original instruction set
+    Not0 = 28,     // Flip the low bit of CX
+    Shl = 29,      // Shift CX left by one bit
+    Zero = 30,     // CX = 0
+    SubAB = 31,    // CX = AX - BX
+    SubAC = 32,    // AX = AX - CX
+    MovAB = 33,    // AX = BX
+    MovBA = 34,    // BX = AX
+    CopyDC = 35,   // DX = CX (register copy; MovDC reads memory)
+    JmpO = 36,     // Jump to the nearest template complement in either direction
+    AdrO = 37,     // Address of the nearest template in either direction
 }
 
 impl Instruction {
-    /// Convert a u8 to an instruction, with invalid values becoming Nop0
-    pub fn from_u8(byte: u8) -> Self {
-        match byte {
-            0 => Instruction::Nop0,
-            1 => Instruction::Nop1,
-            2 => Instruction::IfCZ,
-            3 => Instruction::JmpB,
-            4 => Instruction::JmpF,
-            5 => Instruction::Call,
-            6 => Instruction::Ret,
-            7 => Instruction::MovDC,
-            8 => Instruction::MovCD,
-            9 => Instruction::Adr,
-            10 => Instruction::AdrB,
-            11 => Instruction::AdrF,
-            12 => Instruction::IncA,
-            13 => Instruction::IncB,
-            14 => Instruction::IncC,
-            15 => Instruction::DecC,
-            16 => Instruction::MallocA,
-            17 => Instruction::Divide,
-            18 => Instruction::PushA,
-            19 => Instruction::PushB,
-            20 => Instruction::PushC,
-            21 => Instruction::PushD,
-            22 => Instruction::PopA,
-            23 => Instruction::PopB,
-            24 => Instruction::PopC,
-            25 => Instruction::PopD,
-            26 => Instruction::Halt,
-            _ => Instruction::Nop0, // Invalid instructions become Nop0
-        }
+    /// Number of distinct operations
+    pub const COUNT: usize = 38;
+
+    /// Every operation, indexed by its `u8` code
+    pub const ALL: [Instruction; Self::COUNT] = [
+        Instruction::Nop0, Instruction::Nop1, Instruction::IfCZ, Instruction::JmpB,
+        Instruction::JmpF, Instruction::Call, Instruction::Ret, Instruction::MovDC,
+        Instruction::MovCD, Instruction::Adr, Instruction::AdrB, Instruction::AdrF,
+        Instruction::IncA, Instruction::IncB, Instruction::IncC, Instruction::DecC,
+        Instruction::MallocA, Instruction::Divide, Instruction::PushA, Instruction::PushB,
+        Instruction::PushC, Instruction::PushD, Instruction::PopA, Instruction::PopB,
+        Instruction::PopC, Instruction::PopD, Instruction::Halt, Instruction::MovII,
+        Instruction::Not0, Instruction::Shl, Instruction::Zero, Instruction::SubAB,
+        Instruction::SubAC, Instruction::MovAB, Instruction::MovBA, Instruction::CopyDC,
+        Instruction::JmpO, Instruction::AdrO,
+    ];
+
+    /// Convert a u8 code back to an instruction; unknown codes are rejected
+    pub fn from_u8(byte: u8) -> Option<Self> {
+        Self::ALL.get(byte as usize).copied()
     }
 
     /// Convert instruction to u8
@@ -84,6 +91,51 @@ impl Instruction {
         self as u8
     }
 
+    /// Canonical assembly mnemonic. Instruction sets may spell an operation
+    /// differently; see `Isa::mnemonic`.
+    pub const fn mnemonic(self) -> &'static str {
+        match self {
+            Instruction::Nop0 => "nop0",
+            Instruction::Nop1 => "nop1",
+            Instruction::IfCZ => "if_cz",
+            Instruction::JmpB => "jmp_b",
+            Instruction::JmpF => "jmp_f",
+            Instruction::Call => "call",
+            Instruction::Ret => "ret",
+            Instruction::MovDC => "mov_dc",
+            Instruction::MovCD => "mov_cd",
+            Instruction::Adr => "adr",
+            Instruction::AdrB => "adr_b",
+            Instruction::AdrF => "adr_f",
+            Instruction::IncA => "inc_a",
+            Instruction::IncB => "inc_b",
+            Instruction::IncC => "inc_c",
+            Instruction::DecC => "dec_c",
+            Instruction::MallocA => "malloc_a",
+            Instruction::Divide => "divide",
+            Instruction::PushA => "push_a",
+            Instruction::PushB => "push_b",
+            Instruction::PushC => "push_c",
+            Instruction::PushD => "push_d",
+            Instruction::PopA => "pop_a",
+            Instruction::PopB => "pop_b",
+            Instruction::PopC => "pop_c",
+            Instruction::PopD => "pop_d",
+            Instruction::Halt => "halt",
+            Instruction::MovII => "mov_ii",
+            Instruction::Not0 => "not0",
+            Instruction::Shl => "shl",
+            Instruction::Zero => "zero",
+            Instruction::SubAB => "sub_ab",
+            Instruction::SubAC => "sub_ac",
+            Instruction::MovAB => "mov_ab",
+            Instruction::MovBA => "mov_ba",
+            Instruction::CopyDC => "copy_dc",
+            Instruction::JmpO => "jmp_o",
+            Instruction::AdrO => "adr_o",
+        }
+    }
+
     /// Check if this instruction is a template marker (Nop0 or Nop1)
     pub fn is_template(&self) -> bool {
         matches!(self, Instruction::Nop0 | Instruction::Nop1)
@@ -98,9 +150,3 @@ impl Instruction {
         }
     }
 }
-
-impl Default for Instruction {
-    fn default() -> Self {
-        Instruction::Nop0
-    }
-}
@@ -0,0 +1,161 @@
+use crate::genebank::{Genebank, GenotypeId};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Ecological role of a genotype, judged by whose code it uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Role {
+    /// Runs only its own code
+    SelfSufficient,
+    /// Uses code of other genotypes that do not use its code in return
+    Parasite,
+    /// Uses code of parasites
+    HyperParasite,
+    /// Shares code with its own kind: uses code of other organisms of its
+    /// genotype, or of a genotype that also uses its code
+    Social,
+}
+
+impl Role {
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::SelfSufficient => "self-sufficient",
+            Role::Parasite => "parasite",
+            Role::HyperParasite => "hyper-parasite",
+            Role::Social => "social",
+        }
+    }
+}
+
+/// Counts of organisms of one genotype executing or matching templates in
+/// code belonging to organisms of another
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InteractionMatrix {
+    /// user -> provider -> number of events, nested so snapshots can use
+    /// plain JSON object keys
+    counts: BTreeMap<GenotypeId, BTreeMap<GenotypeId, u64>>,
+}
+
+impl InteractionMatrix {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record genotype `user` using code from genotype `provider`
+    pub fn record(&mut self, user: GenotypeId, provider: GenotypeId) {
+        *self.counts.entry(user).or_default().entry(provider).or_insert(0) += 1;
+    }
+
+    /// Number of times `user` used code from `provider`
+    pub fn count(&self, user: GenotypeId, provider: GenotypeId) -> u64 {
+        self.counts.get(&user).and_then(|row| row.get(&provider)).copied().unwrap_or(0)
+    }
+
+    /// All (user, provider, count) entries
+    pub fn iter(&self) -> impl Iterator<Item = (GenotypeId, GenotypeId, u64)> + '_ {
+        self.counts
+            .iter()
+            .flat_map(|(&user, row)| row.iter().map(move |(&provider, &count)| (user, provider, count)))
+    }
+
+    /// Genotypes whose code `user` has used
+    pub fn providers(&self, user: GenotypeId) -> BTreeSet<GenotypeId> {
+        self.counts.get(&user).map(|row| row.keys().copied().collect()).unwrap_or_default()
+    }
+
+    /// Role of a genotype given every interaction recorded so far
+    pub fn role(&self, genotype: GenotypeId) -> Role {
+        match self.base_role(genotype) {
+            Role::Parasite if self.providers(genotype).iter().any(|&p| self.base_role(p) == Role::Parasite) => {
+                Role::HyperParasite
+            }
+            role => role,
+        }
+    }
+
+    fn base_role(&self, genotype: GenotypeId) -> Role {
+        let providers = self.providers(genotype);
+        if providers.is_empty() {
+            Role::SelfSufficient
+        } else if providers.contains(&genotype) || providers.iter().any(|&p| self.count(p, genotype) > 0) {
+            Role::Social
+        } else {
+            Role::Parasite
+        }
+    }
+
+    /// Roles of the genotypes currently alive
+    pub fn living_roles(&self, genebank: &Genebank) -> BTreeMap<GenotypeId, Role> {
+        genebank.living_ids().map(|id| (id, self.role(id))).collect()
+    }
+
+    /// Number of living genotypes in each role
+    pub fn role_counts(&self, genebank: &Genebank) -> BTreeMap<Role, usize> {
+        let mut counts = BTreeMap::new();
+        for role in self.living_roles(genebank).into_values() {
+            *counts.entry(role).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// One-line summary of the living genotypes' roles
+    pub fn summary(&self, genebank: &Genebank) -> String {
+        let counts = self.role_counts(genebank);
+        [Role::SelfSufficient, Role::Parasite, Role::HyperParasite, Role::Social]
+            .iter()
+            .map(|role| format!("{}={}", role.name(), counts.get(role).copied().unwrap_or(0)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The `n` most frequent interactions, most frequent first
+    pub fn strongest(&self, n: usize) -> Vec<(GenotypeId, GenotypeId, u64)> {
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| (a.0, a.1).cmp(&(b.0, b.1))));
+        entries.truncate(n);
+        entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.counts.values().map(|row| row.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classification() {
+        let (host, parasite, hyper, social_a, social_b, kin) = (0, 1, 2, 3, 4, 5);
+        let mut matrix = InteractionMatrix::new();
+        matrix.record(parasite, host);
+        matrix.record(parasite, host);
+        matrix.record(hyper, parasite);
+        matrix.record(social_a, social_b);
+        matrix.record(social_b, social_a);
+        matrix.record(kin, kin);
+
+        assert_eq!(matrix.count(parasite, host), 2);
+        assert_eq!(matrix.role(host), Role::SelfSufficient);
+        assert_eq!(matrix.role(parasite), Role::Parasite);
+        assert_eq!(matrix.role(hyper), Role::HyperParasite);
+        assert_eq!(matrix.role(social_a), Role::Social);
+        assert_eq!(matrix.role(social_b), Role::Social);
+        assert_eq!(matrix.role(kin), Role::Social);
+        assert_eq!(matrix.strongest(1), vec![(parasite, host, 2)]);
+        assert_eq!(matrix.len(), 5);
+
+        let json = serde_json::to_string(&matrix).unwrap();
+        let restored: InteractionMatrix = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.iter().collect::<Vec<_>>(), matrix.iter().collect::<Vec<_>>());
+    }
+}
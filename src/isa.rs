@@ -0,0 +1,215 @@
+use crate::instruction::Instruction;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// One entry of an opcode table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Opcode {
+    pub mnemonic: &'static str,
+    pub instruction: Instruction,
+}
+
+impl Opcode {
+    /// An opcode spelled with the instruction's canonical mnemonic
+    const fn native(instruction: Instruction) -> Self {
+        Self { mnemonic: instruction.mnemonic(), instruction }
+    }
+
+    const fn named(mnemonic: &'static str, instruction: Instruction) -> Self {
+        Self { mnemonic, instruction }
+    }
+}
+
+/// This simulator's own instruction set
+const TIERRA_RS: [Opcode; 35] = {
+    use Instruction::*;
+    [
+        Opcode::native(Nop0), Opcode::native(Nop1), Opcode::native(IfCZ), Opcode::native(JmpB),
+        Opcode::native(JmpF), Opcode::native(Call), Opcode::native(Ret), Opcode::native(MovDC),
+        Opcode::native(MovCD), Opcode::native(Adr), Opcode::native(AdrB), Opcode::native(AdrF),
+        Opcode::native(IncA), Opcode::native(IncB), Opcode::native(IncC), Opcode::native(DecC),
+        Opcode::native(MallocA), Opcode::native(Divide), Opcode::native(PushA), Opcode::native(PushB),
+        Opcode::native(PushC), Opcode::native(PushD), Opcode::native(PopA), Opcode::native(PopB),
+        Opcode::native(PopC), Opcode::native(PopD), Opcode::native(Halt), Opcode::native(MovII),
+        Opcode::native(Not0), Opcode::native(Shl), Opcode::native(Zero), Opcode::native(SubAB),
+        Opcode::native(SubAC), Opcode::native(MovAB), Opcode::native(MovBA),
+    ]
+};
+
+/// Tierra's instruction set 0, in its original opcode order and spelling.
+/// `mal` and `movii` follow this simulator's register conventions: `mal`
+/// allocates AX cells and leaves the address in BX, and `movii` copies
+/// [AX] to [BX].
+const TIERRA_0: [Opcode; 32] = {
+    use Instruction::*;
+    [
+        Opcode::named("nop0", Nop0),
+        Opcode::named("nop1", Nop1),
+        Opcode::named("not0", Not0),
+        Opcode::named("shl", Shl),
+        Opcode::named("zero", Zero),
+        Opcode::named("ifz", IfCZ),
+        Opcode::named("sub_cab", SubAB),
+        Opcode::named("sub_aac", SubAC),
+        Opcode::named("inc_a", IncA),
+        Opcode::named("inc_b", IncB),
+        Opcode::named("dec_c", DecC),
+        Opcode::named("inc_c", IncC),
+        Opcode::named("push_a", PushA),
+        Opcode::named("push_b", PushB),
+        Opcode::named("push_c", PushC),
+        Opcode::named("push_d", PushD),
+        Opcode::named("pop_a", PopA),
+        Opcode::named("pop_b", PopB),
+        Opcode::named("pop_c", PopC),
+        Opcode::named("pop_d", PopD),
+        Opcode::named("jmpo", JmpO),
+        Opcode::named("jmpb", JmpB),
+        Opcode::named("call", Call),
+        Opcode::named("ret", Ret),
+        Opcode::named("mov_dc", CopyDC),
+        Opcode::named("mov_ba", MovBA),
+        Opcode::named("movii", MovII),
+        Opcode::named("adro", AdrO),
+        Opcode::named("adrb", AdrB),
+        Opcode::named("adrf", AdrF),
+        Opcode::named("mal", MallocA),
+        Opcode::named("divide", Divide),
+    ]
+};
+
+/// An instruction set: which operations exist, the opcode each one is
+/// encoded as, and how it is spelled in genome files. Opcodes are what
+/// organisms see through `MovDC`/`MovCD`, and mutations draw uniformly
+/// from them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Isa {
+    /// This simulator's 35-opcode set
+    #[default]
+    TierraRs,
+    /// Tierra's original 32-opcode set 0
+    Tierra0,
+}
+
+impl Isa {
+    pub const ALL: [Isa; 2] = [Isa::TierraRs, Isa::Tierra0];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Isa::TierraRs => "tierra-rs",
+            Isa::Tierra0 => "tierra0",
+        }
+    }
+
+    /// The opcode table, indexed by opcode
+    pub fn opcodes(self) -> &'static [Opcode] {
+        match self {
+            Isa::TierraRs => &TIERRA_RS,
+            Isa::Tierra0 => &TIERRA_0,
+        }
+    }
+
+    pub fn opcode_count(self) -> usize {
+        self.opcodes().len()
+    }
+
+    /// The instruction an opcode stands for
+    pub fn decode(self, opcode: usize) -> Option<Instruction> {
+        self.opcodes().get(opcode).map(|op| op.instruction)
+    }
+
+    /// The opcode of an instruction, if this set has it
+    pub fn encode(self, inst: Instruction) -> Option<usize> {
+        self.opcodes().iter().position(|op| op.instruction == inst)
+    }
+
+    pub fn contains(self, inst: Instruction) -> bool {
+        self.encode(inst).is_some()
+    }
+
+    /// The instructions of this set, in opcode order
+    pub fn instructions(self) -> impl Iterator<Item = Instruction> {
+        self.opcodes().iter().map(|op| op.instruction)
+    }
+
+    /// A uniformly random opcode's instruction
+    pub fn random(self, rng: &mut impl Rng) -> Instruction {
+        let opcodes = self.opcodes();
+        opcodes[rng.gen_range(0..opcodes.len())].instruction
+    }
+
+    /// How this set spells an instruction; instructions from outside the
+    /// set fall back to their canonical mnemonic
+    pub fn mnemonic(self, inst: Instruction) -> &'static str {
+        self.opcodes()
+            .iter()
+            .find(|op| op.instruction == inst)
+            .map_or(inst.mnemonic(), |op| op.mnemonic)
+    }
+
+    /// Parse a mnemonic of this set. Case and underscores are ignored, so
+    /// `mov_ii`, `MovII` and `movii` are equivalent; `mal` is accepted for
+    /// `malloc_a`.
+    pub fn from_mnemonic(self, text: &str) -> Option<Instruction> {
+        let normalize = |text: &str| -> String {
+            text.chars().filter(|&c| c != '_').map(|c| c.to_ascii_lowercase()).collect()
+        };
+        let wanted = normalize(text);
+        if wanted == "mal" && self.contains(Instruction::MallocA) {
+            return Some(Instruction::MallocA);
+        }
+        self.opcodes()
+            .iter()
+            .find(|op| normalize(op.mnemonic) == wanted)
+            .map(|op| op.instruction)
+    }
+}
+
+impl fmt::Display for Isa {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Isa {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Isa::ALL
+            .into_iter()
+            .find(|isa| isa.name() == s)
+            .ok_or_else(|| format!("Unknown instruction set: {}", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tables_decode_and_encode() {
+        for isa in Isa::ALL {
+            for opcode in 0..isa.opcode_count() {
+                let inst = isa.decode(opcode).unwrap();
+                assert_eq!(isa.encode(inst), Some(opcode), "{} opcode {} is not unique", isa, opcode);
+                assert_eq!(isa.from_mnemonic(isa.mnemonic(inst)), Some(inst));
+            }
+            assert_eq!(isa.decode(isa.opcode_count()), None);
+            assert_eq!(isa.to_string().parse(), Ok(isa));
+        }
+
+        assert_eq!(Isa::TierraRs.opcode_count(), 35);
+        for inst in Isa::TierraRs.instructions() {
+            assert_eq!(Isa::TierraRs.encode(inst), Some(inst.to_u8() as usize), "{:?} code differs", inst);
+        }
+        assert_eq!(Isa::Tierra0.opcode_count(), 32);
+        assert_eq!(Isa::Tierra0.decode(26), Some(Instruction::MovII));
+        assert_eq!(Isa::Tierra0.from_mnemonic("subCAB"), Some(Instruction::SubAB));
+        assert_eq!(Isa::Tierra0.mnemonic(Instruction::CopyDC), "mov_dc");
+        assert_eq!(Isa::TierraRs.from_mnemonic("jmpo"), None);
+        assert_eq!(Isa::TierraRs.mnemonic(Instruction::JmpO), "jmp_o");
+        assert!("tierra5".parse::<Isa>().is_err());
+    }
+}
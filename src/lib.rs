@@ -1,8 +1,26 @@
 pub mod instruction;
+pub mod isa;
+pub mod topology;
+pub mod allocator;
 pub mod memory;
+pub mod template_index;
+pub mod mutation;
 pub mod organism;
+pub mod permissions;
+pub mod profile;
 pub mod cpu;
 pub mod scheduler;
+pub mod reaper;
 pub mod stats;
+pub mod genebank;
+pub mod lineage;
+pub mod diversity;
+pub mod interaction;
+pub mod metrics;
+pub mod assembler;
 pub mod simulator;
+pub mod debugger;
+pub mod snapshot;
+pub mod batch;
+pub mod headless;
 pub mod ui;
@@ -0,0 +1,304 @@
+use crate::genebank::{Genebank, GenotypeId};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// One organism in the ancestry graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineageNode {
+    pub id: usize,
+    /// Nearest recorded ancestor; differs from the organism's real parent
+    /// once dead single-child ancestors have been spliced out
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    pub generation: usize,
+    pub genotype: Option<GenotypeId>,
+    pub size: usize,
+    /// Instruction count at birth
+    pub born: u64,
+    /// Instruction count at death
+    pub died: Option<u64>,
+}
+
+impl LineageNode {
+    pub fn is_alive(&self) -> bool {
+        self.died.is_none()
+    }
+}
+
+/// Ancestry graph of the organisms in a run. Dead organisms are kept only
+/// while they have living descendants, and a dead ancestor with a single
+/// remaining child is spliced out, so the graph stays proportional to the
+/// living population rather than the whole history.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Lineage {
+    nodes: BTreeMap<usize, LineageNode>,
+}
+
+impl Lineage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a birth. Organisms without a recorded parent become roots.
+    pub fn record_birth(
+        &mut self,
+        id: usize,
+        parent: Option<usize>,
+        generation: usize,
+        genotype: Option<GenotypeId>,
+        size: usize,
+        time: u64,
+    ) {
+        let parent = parent.filter(|p| self.nodes.contains_key(p));
+        if let Some(p) = parent {
+            self.nodes.get_mut(&p).unwrap().children.push(id);
+        }
+        self.nodes.insert(id, LineageNode {
+            id,
+            parent,
+            children: Vec::new(),
+            generation,
+            genotype,
+            size,
+            born: time,
+            died: None,
+        });
+    }
+
+    /// Record a death and prune whatever ancestry no longer leads to a living organism
+    pub fn record_death(&mut self, id: usize, time: u64) {
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.died = Some(time);
+            self.prune(id);
+        }
+    }
+
+    fn prune(&mut self, mut id: usize) {
+        while let Some(node) = self.nodes.get(&id) {
+            if node.is_alive() {
+                return;
+            }
+            let parent = node.parent;
+            match node.children.len() {
+                0 => {
+                    self.nodes.remove(&id);
+                    let Some(p) = parent else { return };
+                    self.nodes.get_mut(&p).unwrap().children.retain(|&c| c != id);
+                    id = p;
+                }
+                // Splice out the link, keeping roots so trees stay anchored at their seed
+                1 if parent.is_some() => {
+                    let child = node.children[0];
+                    let p = parent.unwrap();
+                    self.nodes.remove(&id);
+                    self.nodes.get_mut(&child).unwrap().parent = Some(p);
+                    for c in self.nodes.get_mut(&p).unwrap().children.iter_mut() {
+                        if *c == id {
+                            *c = child;
+                        }
+                    }
+                    return;
+                }
+                _ => return,
+            }
+        }
+    }
+
+    pub fn get(&self, id: usize) -> Option<&LineageNode> {
+        self.nodes.get(&id)
+    }
+
+    /// Nodes in ID (birth) order
+    pub fn iter(&self) -> impl Iterator<Item = &LineageNode> {
+        self.nodes.values()
+    }
+
+    /// Nodes without a recorded ancestor
+    pub fn roots(&self) -> impl Iterator<Item = &LineageNode> {
+        self.nodes.values().filter(|node| node.parent.is_none())
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+    }
+
+    /// Newick tree, with branch lengths in instructions between births.
+    /// Several roots are joined under an unnamed root.
+    pub fn to_newick(&self, genebank: &Genebank) -> String {
+        enum Visit {
+            Enter(usize),
+            Exit(usize),
+            Comma,
+        }
+
+        let roots: Vec<usize> = self.roots().map(|node| node.id).collect();
+        let mut out = String::new();
+        let mut stack = Vec::new();
+        for (i, &root) in roots.iter().enumerate().rev() {
+            stack.push(Visit::Enter(root));
+            if i > 0 {
+                stack.push(Visit::Comma);
+            }
+        }
+
+        // Iterative traversal: lineages can be thousands of generations deep
+        while let Some(visit) = stack.pop() {
+            match visit {
+                Visit::Enter(id) => {
+                    let node = &self.nodes[&id];
+                    if node.children.is_empty() {
+                        self.write_newick_label(&mut out, node, genebank);
+                    } else {
+                        out.push('(');
+                        stack.push(Visit::Exit(id));
+                        for (i, &child) in node.children.iter().enumerate().rev() {
+                            stack.push(Visit::Enter(child));
+                            if i > 0 {
+                                stack.push(Visit::Comma);
+                            }
+                        }
+                    }
+                }
+                Visit::Exit(id) => {
+                    out.push(')');
+                    self.write_newick_label(&mut out, &self.nodes[&id], genebank);
+                }
+                Visit::Comma => out.push(','),
+            }
+        }
+
+        if roots.len() > 1 {
+            out = format!("({})", out);
+        }
+        out.push(';');
+        out
+    }
+
+    fn write_newick_label(&self, out: &mut String, node: &LineageNode, genebank: &Genebank) {
+        out.push_str(&node_label(node, genebank));
+        if let Some(parent) = node.parent.and_then(|p| self.nodes.get(&p)) {
+            let _ = write!(out, ":{}", node.born - parent.born);
+        }
+    }
+
+    /// GraphML document with one node per organism and parent -> child edges
+    pub fn to_graphml(&self, genebank: &Genebank) -> String {
+        let mut out = String::new();
+        out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        for (key, kind) in [
+            ("label", "string"),
+            ("genotype", "string"),
+            ("generation", "int"),
+            ("size", "int"),
+            ("born", "long"),
+            ("died", "long"),
+        ] {
+            let _ = writeln!(out, "  <key id=\"{0}\" for=\"node\" attr.name=\"{0}\" attr.type=\"{1}\"/>", key, kind);
+        }
+        out.push_str("  <graph id=\"lineage\" edgedefault=\"directed\">\n");
+
+        for node in self.nodes.values() {
+            let _ = writeln!(out, "    <node id=\"n{}\">", node.id);
+            let _ = writeln!(out, "      <data key=\"label\">{}</data>", node_label(node, genebank));
+            if let Some(genotype) = node.genotype.and_then(|g| genebank.get(g)) {
+                let _ = writeln!(out, "      <data key=\"genotype\">{}</data>", genotype.name);
+            }
+            let _ = writeln!(out, "      <data key=\"generation\">{}</data>", node.generation);
+            let _ = writeln!(out, "      <data key=\"size\">{}</data>", node.size);
+            let _ = writeln!(out, "      <data key=\"born\">{}</data>", node.born);
+            if let Some(died) = node.died {
+                let _ = writeln!(out, "      <data key=\"died\">{}</data>", died);
+            }
+            out.push_str("    </node>\n");
+        }
+        for node in self.nodes.values() {
+            if let Some(parent) = node.parent {
+                let _ = writeln!(out, "    <edge source=\"n{}\" target=\"n{}\"/>", parent, node.id);
+            }
+        }
+
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+
+    /// Write the tree to `path`, as GraphML if it ends in `.graphml` and Newick otherwise
+    pub fn save(&self, genebank: &Genebank, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        let text = if path.extension().is_some_and(|ext| ext == "graphml") {
+            self.to_graphml(genebank)
+        } else {
+            self.to_newick(genebank)
+        };
+        fs::write(path, text).map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+    }
+}
+
+/// Organism ID, followed by its genotype name when known
+fn node_label(node: &LineageNode, genebank: &Genebank) -> String {
+    match node.genotype.and_then(|g| genebank.get(g)) {
+        Some(genotype) => format!("{}_{}", node.id, genotype.name),
+        None => node.id.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pruning_and_export() {
+        let genebank = Genebank::new();
+        let mut lineage = Lineage::new();
+        // 0 -> 1 -> {2, 3}, 0 -> 4
+        lineage.record_birth(0, None, 0, None, 80, 0);
+        lineage.record_birth(1, Some(0), 1, None, 80, 10);
+        lineage.record_birth(2, Some(1), 2, None, 80, 25);
+        lineage.record_birth(3, Some(1), 2, None, 80, 30);
+        lineage.record_birth(4, Some(0), 1, None, 80, 40);
+        assert_eq!(lineage.to_newick(&genebank), "((2:15,3:20)1:10,4:40)0;");
+
+        // A dead leaf disappears; a dead ancestor with living descendants stays
+        lineage.record_death(4, 50);
+        lineage.record_death(1, 55);
+        assert_eq!(lineage.to_newick(&genebank), "((2:15,3:20)1:10)0;");
+        assert!(lineage.get(1).is_some());
+
+        // Once 1 has a single child left it is spliced out, and 2 hangs off the root
+        lineage.record_death(3, 60);
+        assert!(lineage.get(1).is_none());
+        assert_eq!(lineage.get(2).unwrap().parent, Some(0));
+        assert_eq!(lineage.to_newick(&genebank), "(2:25)0;");
+
+        // Extinction empties the graph
+        lineage.record_death(2, 70);
+        lineage.record_death(0, 80);
+        assert!(lineage.is_empty());
+        assert_eq!(lineage.to_newick(&genebank), ";");
+    }
+
+    #[test]
+    fn test_forest_and_graphml() {
+        let genebank = Genebank::new();
+        let mut lineage = Lineage::new();
+        lineage.record_birth(0, None, 0, None, 80, 0);
+        lineage.record_birth(1, None, 0, None, 45, 0);
+        lineage.record_birth(2, Some(1), 1, None, 45, 7);
+        assert_eq!(lineage.to_newick(&genebank), "(0,(2:7)1);");
+
+        let graphml = lineage.to_graphml(&genebank);
+        assert_eq!(graphml.matches("<node ").count(), 3);
+        assert!(graphml.contains("<edge source=\"n1\" target=\"n2\"/>"));
+    }
+}
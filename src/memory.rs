@@ -1,24 +1,51 @@
 use crate::instruction::Instruction;
+use crate::allocator::{FreeList, Placement};
+use crate::isa::Isa;
+use crate::template_index::TemplateIndex;
+use crate::topology::{Span, Topology};
 use rand::Rng;
+use std::ops::Range;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 /// The memory "soup" where organisms live
 pub struct Memory {
     data: Vec<Instruction>,
     size: usize,
-    // Track which memory cells are allocated
-    allocated: Vec<bool>,
+    // Free regions; everything else is allocated
+    blocks: FreeList,
+    // Where the nops are, for template searches
+    templates: TemplateIndex,
+    topology: Topology,
 }
 
 impl Memory {
     /// Create a new memory soup of given size
     pub fn new(size: usize) -> Self {
+        Self::with_topology(size, Topology::Circular)
+    }
+
+    /// Create a new memory soup of given size and shape
+    pub fn with_topology(size: usize, topology: Topology) -> Self {
+        let data = vec![Instruction::Nop0; size];
         Self {
-            data: vec![Instruction::Nop0; size],
+            templates: TemplateIndex::new(&data),
+            data,
             size,
-            allocated: vec![false; size],
+            blocks: FreeList::new(size),
+            topology,
         }
     }
 
+    /// The shape of the address space
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Distance between two cells along the topology
+    pub fn distance(&self, a: usize, b: usize) -> usize {
+        self.topology.distance(a, b, self.size)
+    }
+
     /// Get the size of memory
     pub fn size(&self) -> usize {
         self.size
@@ -31,7 +58,9 @@ impl Memory {
 
     /// Write an instruction at an address (wraps around)
     pub fn write(&mut self, addr: usize, inst: Instruction) {
-        self.data[addr % self.size] = inst;
+        let addr = addr % self.size;
+        self.templates.update(addr, self.data[addr], inst);
+        self.data[addr] = inst;
     }
 
     /// Normalize an address to be within bounds
@@ -42,6 +71,45 @@ impl Memory {
     /// Find the next template match in forward direction
     /// Returns the address after the template
     pub fn find_template_forward(&self, start: usize, template: &[Instruction], max_search: usize) -> Option<usize> {
+        let complement = complement(template);
+        match self.topology.search_spans(start, complement.len(), max_search, true, self.size) {
+            Some(groups) => self.find_in_spans(start, &groups, &complement),
+            None => self.templates.find_forward(&self.data, start, &complement, max_search),
+        }
+        .map(|addr| self.normalize_addr(addr + complement.len()))
+    }
+
+    /// Find the next template match in backward direction
+    /// Returns the address after the template
+    pub fn find_template_backward(&self, start: usize, template: &[Instruction], max_search: usize) -> Option<usize> {
+        let complement = complement(template);
+        match self.topology.search_spans(start, complement.len(), max_search, false, self.size) {
+            Some(groups) => self.find_in_spans(start, &groups, &complement),
+            None => self.templates.find_backward(&self.data, start, &complement, max_search),
+        }
+        .map(|addr| self.normalize_addr(addr + complement.len()))
+    }
+
+    /// First cell of the match nearest `start` in the first group of spans
+    /// that has one
+    fn find_in_spans(&self, start: usize, groups: &[Vec<Span>], pattern: &[Instruction]) -> Option<usize> {
+        if pattern.is_empty() {
+            return None;
+        }
+        groups.iter().find_map(|group| {
+            group
+                .iter()
+                .filter_map(|span| match span.upward {
+                    true => self.templates.first_match(&self.data, span.lo, span.hi, pattern),
+                    false => self.templates.last_match(&self.data, span.lo, span.hi, pattern),
+                })
+                .min_by_key(|&p| self.distance(start, p))
+        })
+    }
+
+    /// `find_template_forward` by reading every cell within `max_search`,
+    /// the reference the template index must agree with
+    pub fn find_template_forward_linear(&self, start: usize, template: &[Instruction], max_search: usize) -> Option<usize> {
         if template.is_empty() {
             return None;
         }
@@ -74,9 +142,8 @@ impl Memory {
         None
     }
 
-    /// Find the next template match in backward direction
-    /// Returns the address after the template
-    pub fn find_template_backward(&self, start: usize, template: &[Instruction], max_search: usize) -> Option<usize> {
+    /// `find_template_backward` by reading every cell within `max_search`
+    pub fn find_template_backward_linear(&self, start: usize, template: &[Instruction], max_search: usize) -> Option<usize> {
         if template.is_empty() {
             return None;
         }
@@ -114,64 +181,53 @@ impl Memory {
         None
     }
 
-    /// Allocate a contiguous block of memory
-    /// Returns the start address if successful
-    pub fn allocate(&mut self, size: usize, rng: &mut impl Rng) -> Option<usize> {
-        if size == 0 || size > self.size {
-            return None;
-        }
-
-        // Try random positions
-        for _ in 0..100 {
-            let start = rng.gen_range(0..self.size);
-            if self.is_range_free(start, size) {
-                // Double-check that it's really free before marking
-                debug_assert!(self.is_range_free(start, size),
-                    "BUG: Trying to allocate already-allocated memory at {}", start);
-                self.mark_allocated(start, size, true);
-                // Verify it was marked
-                debug_assert!(!self.is_range_free(start, size),
-                    "BUG: Memory at {} wasn't marked as allocated", start);
-                return Some(start);
+    /// Find the nearest template match in either direction, preferring
+    /// forward on a tie. Returns the address after the template.
+    pub fn find_template_nearest(&self, start: usize, template: &[Instruction], max_search: usize) -> Option<usize> {
+        let forward = self.find_template_forward(start, template, max_search);
+        let backward = self.find_template_backward(start, template, max_search);
+        // Distance from `start` to the first cell of each match
+        let distance = |end: usize, forward: bool| {
+            let first = end + self.size - template.len();
+            if self.topology != Topology::Circular {
+                self.distance(start, first % self.size)
+            } else if forward {
+                (first + self.size - start) % self.size
+            } else {
+                (start + self.size - first % self.size) % self.size
             }
+        };
+        match (forward, backward) {
+            (Some(f), Some(b)) if distance(b, false) < distance(f, true) => Some(b),
+            (Some(f), _) => Some(f),
+            (None, b) => b,
         }
+    }
 
-        // Linear search as fallback
-        for start in 0..self.size {
-            if self.is_range_free(start, size) {
-                debug_assert!(self.is_range_free(start, size),
-                    "BUG: Trying to allocate already-allocated memory at {}", start);
-                self.mark_allocated(start, size, true);
-                debug_assert!(!self.is_range_free(start, size),
-                    "BUG: Memory at {} wasn't marked as allocated", start);
-                return Some(start);
-            }
+    /// Allocate a contiguous block of memory wherever `placement` puts it;
+    /// `near` is the address `NearMother` searches forward from.
+    /// Returns the start address if successful
+    pub fn allocate(&mut self, size: usize, placement: Placement, near: usize, rng: &mut impl Rng) -> Option<usize> {
+        match self.topology.block_starts(size, self.size) {
+            Some(starts) => self.blocks.allocate_in(size, placement, near, &starts, rng),
+            None => self.blocks.allocate(size, placement, near, rng),
         }
-
-        None
     }
 
-    /// Check if a memory range is free
-    fn is_range_free(&self, start: usize, size: usize) -> bool {
-        for i in 0..size {
-            if self.allocated[self.normalize_addr(start + i)] {
-                return false;
-            }
-        }
-        true
+    /// `allocate`, with the block starting within one of the ranges `starts`
+    pub fn allocate_in(&mut self, size: usize, placement: Placement, near: usize, starts: &[Range<usize>], rng: &mut impl Rng) -> Option<usize> {
+        self.blocks.allocate_in(size, placement, near, starts, rng)
     }
 
-    /// Mark a range as allocated or free
-    pub fn mark_allocated(&mut self, start: usize, size: usize, allocated: bool) {
-        for i in 0..size {
-            let addr = self.normalize_addr(start + i);
-            self.allocated[addr] = allocated;
-        }
+    /// Allocate exactly the block at `start` if it is free and the topology
+    /// allows a block there
+    pub fn allocate_at(&mut self, start: usize, size: usize) -> bool {
+        size > 0 && self.topology.allows_block(start, size, self.size) && self.blocks.reserve(start, size)
     }
 
     /// Free a memory block
     pub fn free(&mut self, start: usize, size: usize) {
-        self.mark_allocated(start, size, false);
+        self.blocks.release(start, size);
     }
 
     /// Copy a block of memory from source to destination
@@ -185,12 +241,16 @@ impl Memory {
         }
     }
 
-    /// Apply mutation to a memory cell with given probability
-    pub fn maybe_mutate(&mut self, addr: usize, mutation_rate: f64, rng: &mut impl Rng) {
+    /// Apply mutation to a memory cell with given probability.
+    /// Returns true if the cell's instruction actually changed.
+    pub fn maybe_mutate(&mut self, addr: usize, mutation_rate: f64, isa: Isa, rng: &mut impl Rng) -> bool {
         if rng.gen::<f64>() < mutation_rate {
-            let random_byte = rng.gen::<u8>() % 27; // We have 27 instructions
-            let random_inst = Instruction::from_u8(random_byte);
-            self.write(addr, random_inst);
+            let old = self.read(addr);
+            let new = isa.random(rng);
+            self.write(addr, new);
+            new != old
+        } else {
+            false
         }
     }
 
@@ -203,6 +263,245 @@ impl Memory {
 
     /// Count free cells
     pub fn count_free_cells(&self) -> usize {
-        self.allocated.iter().filter(|&&x| !x).count()
+        self.size - self.blocks.used()
+    }
+
+    /// Count allocated cells
+    pub fn count_used_cells(&self) -> usize {
+        self.blocks.used()
+    }
+
+    /// Length of the largest block MallocA could currently be given
+    pub fn largest_free_block(&self) -> usize {
+        self.blocks.largest()
+    }
+
+    /// Number of separate free regions
+    pub fn free_block_count(&self) -> usize {
+        self.blocks.holes()
+    }
+
+    /// Slide the given blocks, keeping their address order, down to the
+    /// start of the soup so that free memory forms a single region at the
+    /// end. Allocated cells outside `blocks` are freed; free cells keep
+    /// whatever they held. Returns each block's new start, in the order given.
+    pub fn compact(&mut self, blocks: &[(usize, usize)]) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..blocks.len()).collect();
+        order.sort_by_key(|&i| blocks[i].0 % self.size);
+        // Blocks may overlap their own new place, so read everything first
+        let contents: Vec<Vec<Instruction>> = blocks.iter().map(|&(start, len)| self.get_slice(start, len)).collect();
+
+        self.blocks = FreeList::new(self.size);
+        let mut starts = vec![0; blocks.len()];
+        let mut next = 0;
+        for i in order {
+            starts[i] = next;
+            for (offset, &inst) in contents[i].iter().enumerate() {
+                self.write(next + offset, inst);
+            }
+            let reserved = self.blocks.reserve(next, contents[i].len());
+            debug_assert!(reserved, "compacted blocks overlap");
+            next += contents[i].len();
+        }
+        starts
+    }
+
+    /// Allocated blocks as (start, length) runs, in address order
+    pub fn allocated_runs(&self) -> Vec<(usize, usize)> {
+        let mut runs = Vec::new();
+        let mut next = 0;
+        for (start, len) in self.blocks.regions() {
+            if start > next {
+                runs.push((next, start - next));
+            }
+            next = start + len;
+        }
+        if next < self.size {
+            runs.push((next, self.size - next));
+        }
+        runs
+    }
+}
+
+/// The nops that match a template
+fn complement(template: &[Instruction]) -> Vec<Instruction> {
+    template.iter().filter_map(|inst| inst.complement()).collect()
+}
+
+/// Compact on-disk form of the soup: opcodes as bytes, allocation map as runs
+#[derive(Serialize, Deserialize)]
+struct MemoryState {
+    size: usize,
+    data: Vec<u8>,
+    allocated: Vec<(usize, usize)>,
+    #[serde(default)]
+    topology: Topology,
+}
+
+impl Serialize for Memory {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        MemoryState {
+            size: self.size,
+            data: self.data.iter().map(|inst| inst.to_u8()).collect(),
+            allocated: self.allocated_runs(),
+            topology: self.topology,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Memory {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let state = MemoryState::deserialize(deserializer)?;
+        if state.size == 0 || state.data.len() != state.size {
+            return Err(serde::de::Error::custom(format!(
+                "memory size {} does not match {} data cells",
+                state.size,
+                state.data.len()
+            )));
+        }
+
+        state.topology.check(state.size).map_err(serde::de::Error::custom)?;
+        let mut memory = Memory::with_topology(state.size, state.topology);
+        for (addr, &byte) in state.data.iter().enumerate() {
+            memory.data[addr] = Instruction::from_u8(byte)
+                .ok_or_else(|| serde::de::Error::custom(format!("invalid instruction {} at {}", byte, addr)))?;
+        }
+        for (start, len) in state.allocated {
+            if !memory.blocks.reserve(start, len) {
+                return Err(serde::de::Error::custom(format!("allocated block {}+{} overlaps another", start, len)));
+            }
+        }
+        memory.templates = TemplateIndex::new(&memory.data);
+        Ok(memory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// Mostly nops, so that templates have plenty of partial matches
+    fn random_cell(rng: &mut StdRng) -> Instruction {
+        match rng.gen_range(0..5) {
+            0 | 1 => Instruction::Nop0,
+            2 | 3 => Instruction::Nop1,
+            _ => Isa::TierraRs.random(rng),
+        }
+    }
+
+    #[test]
+    fn test_deserialize_rejects_overlapping_blocks() {
+        let mut memory = Memory::new(64);
+        assert!(memory.allocate_at(5, 10));
+        let mut state: serde_json::Value = serde_json::to_value(&memory).unwrap();
+        let block = state["allocated"][0].clone();
+        state["allocated"].as_array_mut().unwrap().push(block);
+        let error = serde_json::from_value::<Memory>(state).err().unwrap();
+        assert!(error.to_string().contains("overlaps"), "{}", error);
+    }
+
+    #[test]
+    fn test_compact_packs_blocks_in_address_order() {
+        let mut memory = Memory::new(100);
+        let mut rng = StdRng::seed_from_u64(1);
+        let blocks = [(60, 10), (95, 10), (20, 5)];
+        for (n, &(start, len)) in blocks.iter().enumerate() {
+            assert!(memory.allocate_at(start, len));
+            for i in 0..len {
+                memory.write(start + i, Instruction::from_u8(n as u8 + 2).unwrap());
+            }
+        }
+        assert!(memory.allocate_at(40, 3)); // not listed, so freed
+        assert_eq!((memory.free_block_count(), memory.largest_free_block()), (4, 25));
+        assert!(memory.allocate(40, Placement::FirstFit, 0, &mut rng).is_none());
+
+        // The block at 95 wraps around the end of the soup
+        assert_eq!(memory.compact(&blocks), vec![5, 15, 0]);
+        assert_eq!(memory.allocated_runs(), vec![(0, 25)]);
+        assert_eq!((memory.free_block_count(), memory.largest_free_block()), (1, 75));
+        for (n, (&(_, len), start)) in blocks.iter().zip([5, 15, 0]).enumerate() {
+            assert_eq!(memory.get_slice(start, len), vec![Instruction::from_u8(n as u8 + 2).unwrap(); len]);
+        }
+        assert_eq!(memory.allocate(40, Placement::FirstFit, 0, &mut rng), Some(25));
+    }
+
+    #[test]
+    fn test_searches_follow_the_topology() {
+        // A soup of Ret with the complement of `template` written at `at`
+        let soup = |topology: Topology, at: &[usize]| {
+            let mut memory = Memory::with_topology(100, topology);
+            for addr in 0..100 {
+                memory.write(addr, Instruction::Ret);
+            }
+            for &addr in at {
+                memory.write(addr, Instruction::Nop1);
+                memory.write(addr + 1, Instruction::Nop1);
+            }
+            memory
+        };
+        let template = [Instruction::Nop0, Instruction::Nop0];
+
+        let circular = soup(Topology::Circular, &[2]);
+        assert_eq!(circular.find_template_forward(90, &template, 200), Some(4));
+        let linear = soup(Topology::Linear, &[2]);
+        assert_eq!(linear.find_template_forward(90, &template, 200), None);
+        assert_eq!(linear.find_template_backward(50, &template, 200), Some(4));
+
+        // Searches wrap around their own island and never leave it
+        let islands = soup(Topology::Islands { count: 4, migration_rate: 0.0 }, &[2]);
+        assert_eq!(islands.find_template_forward(10, &template, 200), Some(4));
+        assert_eq!(islands.find_template_backward(30, &template, 200), None);
+        let mut empty = Memory::with_topology(100, islands.topology());
+        assert!(!empty.allocate_at(24, 2) && empty.allocate_at(23, 2));
+
+        // On a 10-wide torus the next nine cells come first, then the row
+        // below within the radius
+        let torus = Topology::Torus { width: 10, radius: 1 };
+        assert_eq!(soup(torus, &[13]).find_template_forward(2, &template, 200), Some(15));
+        assert_eq!(soup(torus, &[16]).find_template_forward(2, &template, 200), None);
+        assert_eq!(soup(torus, &[13]).find_template_backward(22, &template, 200), Some(15));
+        assert_eq!(soup(torus, &[12, 7]).find_template_forward(2, &template, 200), Some(9));
+        assert_eq!(soup(torus, &[12]).find_template_nearest(22, &template, 200), Some(14));
+    }
+
+    #[test]
+    fn test_template_index_matches_linear_scan() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..300 {
+            // Up to a few bitmap words
+            let size = rng.gen_range(1..300);
+            let mut memory = Memory::new(size);
+            for addr in 0..size {
+                memory.write(addr, random_cell(&mut rng));
+            }
+
+            for _ in 0..50 {
+                // Keep rewriting cells so runs are split and merged
+                for _ in 0..rng.gen_range(0..4) {
+                    let addr = rng.gen_range(0..size);
+                    memory.write(addr, random_cell(&mut rng));
+                }
+                assert_eq!(memory.templates, TemplateIndex::new(&memory.data));
+
+                let template: Vec<Instruction> = (0..rng.gen_range(0..7))
+                    .map(|_| if rng.gen() { Instruction::Nop0 } else { Instruction::Nop1 })
+                    .collect();
+                let start = rng.gen_range(0..size);
+                let max_search = rng.gen_range(0..=size);
+                assert_eq!(
+                    memory.find_template_forward(start, &template, max_search),
+                    memory.find_template_forward_linear(start, &template, max_search),
+                    "forward {:?} from {} within {} in {:?}", template, start, max_search, memory.data
+                );
+                assert_eq!(
+                    memory.find_template_backward(start, &template, max_search),
+                    memory.find_template_backward_linear(start, &template, max_search),
+                    "backward {:?} from {} within {} in {:?}", template, start, max_search, memory.data
+                );
+            }
+        }
     }
 }
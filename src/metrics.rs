@@ -0,0 +1,318 @@
+use crate::diversity::{Diversity, DiversityTracker};
+use crate::simulator::Simulator;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+/// A quantity that can be sampled into a metrics time series
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Instructions,
+    Population,
+    MeanSize,
+    MedianSize,
+    /// Number of living genotypes
+    Genotypes,
+    Births,
+    Deaths,
+    Mutations,
+    MemoryUsed,
+    SizeClasses,
+    Shannon,
+    Simpson,
+    /// Genotypes that appeared since the previous sample
+    NewGenotypes,
+    /// Genotypes that went extinct since the previous sample
+    ExtinctGenotypes,
+    Turnover,
+    DominantShare,
+    /// Longest free region of the soup
+    LargestFreeBlock,
+    /// Number of free regions of the soup
+    FreeBlocks,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 18] = [
+        Metric::Instructions,
+        Metric::Population,
+        Metric::MeanSize,
+        Metric::MedianSize,
+        Metric::Genotypes,
+        Metric::Births,
+        Metric::Deaths,
+        Metric::Mutations,
+        Metric::MemoryUsed,
+        Metric::SizeClasses,
+        Metric::Shannon,
+        Metric::Simpson,
+        Metric::NewGenotypes,
+        Metric::ExtinctGenotypes,
+        Metric::Turnover,
+        Metric::DominantShare,
+        Metric::LargestFreeBlock,
+        Metric::FreeBlocks,
+    ];
+
+    /// Column name in exported files
+    pub fn name(self) -> &'static str {
+        match self {
+            Metric::Instructions => "instructions",
+            Metric::Population => "population",
+            Metric::MeanSize => "mean_size",
+            Metric::MedianSize => "median_size",
+            Metric::Genotypes => "genotypes",
+            Metric::Births => "births",
+            Metric::Deaths => "deaths",
+            Metric::Mutations => "mutations",
+            Metric::MemoryUsed => "memory_used",
+            Metric::SizeClasses => "size_classes",
+            Metric::Shannon => "shannon",
+            Metric::Simpson => "simpson",
+            Metric::NewGenotypes => "new_genotypes",
+            Metric::ExtinctGenotypes => "extinct_genotypes",
+            Metric::Turnover => "turnover",
+            Metric::DominantShare => "dominant_share",
+            Metric::LargestFreeBlock => "largest_free_block",
+            Metric::FreeBlocks => "free_blocks",
+        }
+    }
+
+    /// Current value of this metric; diversity metrics come from `diversity`,
+    /// measured over the sampling interval
+    pub fn sample(self, sim: &Simulator, diversity: &Diversity) -> MetricValue {
+        let stats = &sim.stats;
+        match self {
+            Metric::Instructions => MetricValue::Count(stats.total_instructions),
+            Metric::Population => MetricValue::Count(sim.organisms.iter().filter(|o| o.alive).count() as u64),
+            Metric::MeanSize => {
+                let sizes = living_sizes(sim);
+                let mean = if sizes.is_empty() { 0.0 } else { sizes.iter().sum::<usize>() as f64 / sizes.len() as f64 };
+                MetricValue::Real(mean)
+            }
+            Metric::MedianSize => {
+                let sizes = living_sizes(sim);
+                let median = match sizes.len() {
+                    0 => 0.0,
+                    n if n % 2 == 1 => sizes[n / 2] as f64,
+                    n => (sizes[n / 2 - 1] + sizes[n / 2]) as f64 / 2.0,
+                };
+                MetricValue::Real(median)
+            }
+            Metric::Genotypes => MetricValue::Count(sim.genebank.living_count() as u64),
+            Metric::Births => MetricValue::Count(stats.total_organisms_created),
+            Metric::Deaths => MetricValue::Count(stats.total_organisms_died),
+            Metric::Mutations => MetricValue::Count(stats.total_mutations),
+            Metric::MemoryUsed => MetricValue::Count(sim.memory.count_used_cells() as u64),
+            Metric::SizeClasses => MetricValue::Count(diversity.size_classes as u64),
+            Metric::Shannon => MetricValue::Real(diversity.shannon),
+            Metric::Simpson => MetricValue::Real(diversity.simpson),
+            Metric::NewGenotypes => MetricValue::Count(diversity.appeared as u64),
+            Metric::ExtinctGenotypes => MetricValue::Count(diversity.extinct as u64),
+            Metric::Turnover => MetricValue::Real(diversity.turnover),
+            Metric::DominantShare => MetricValue::Real(diversity.dominant_share),
+            Metric::LargestFreeBlock => MetricValue::Count(sim.memory.largest_free_block() as u64),
+            Metric::FreeBlocks => MetricValue::Count(sim.memory.free_block_count() as u64),
+        }
+    }
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Metric::ALL
+            .into_iter()
+            .find(|metric| metric.name() == s)
+            .ok_or_else(|| format!("Unknown metric: {}", s))
+    }
+}
+
+/// Sorted sizes of the living organisms
+fn living_sizes(sim: &Simulator) -> Vec<usize> {
+    let mut sizes: Vec<usize> = sim.organisms.iter().filter(|o| o.alive).map(|o| o.size).collect();
+    sizes.sort_unstable();
+    sizes
+}
+
+/// A sampled value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetricValue {
+    Count(u64),
+    Real(f64),
+}
+
+impl MetricValue {
+    pub fn as_f64(self) -> f64 {
+        match self {
+            MetricValue::Count(n) => n as f64,
+            MetricValue::Real(x) => x,
+        }
+    }
+}
+
+impl fmt::Display for MetricValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetricValue::Count(n) => write!(f, "{}", n),
+            MetricValue::Real(x) => write!(f, "{:.4}", x),
+        }
+    }
+}
+
+/// Output file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsFormat {
+    Csv,
+    JsonLines,
+}
+
+impl MetricsFormat {
+    /// JSON Lines for `.jsonl` paths, CSV otherwise. A `.json` path is
+    /// rejected, since the file would not hold a single JSON document.
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("jsonl") => Ok(MetricsFormat::JsonLines),
+            Some("json") => Err(format!("{} is not a JSON document; use .jsonl for JSON Lines", path.display())),
+            _ => Ok(MetricsFormat::Csv),
+        }
+    }
+}
+
+/// Parse a comma-separated list of metric names
+pub fn parse_fields(list: &str) -> Result<Vec<Metric>, String> {
+    list.split(',').map(|name| name.trim().parse()).collect()
+}
+
+/// Streams a sample of the chosen metrics every `every` instructions
+pub struct MetricsSink<W: Write> {
+    writer: W,
+    format: MetricsFormat,
+    fields: Vec<Metric>,
+    every: u64,
+    next_sample: u64,
+    last_sample: Option<u64>,
+    last_values: Vec<(Metric, MetricValue)>,
+    diversity: DiversityTracker,
+}
+
+impl MetricsSink<BufWriter<File>> {
+    /// Create (or truncate) a metrics file, choosing the format from its extension
+    pub fn create(path: impl AsRef<Path>, fields: Vec<Metric>, every: u64) -> Result<Self, String> {
+        let path = path.as_ref();
+        let format = MetricsFormat::from_path(path)?;
+        let file = File::create(path).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+        Self::new(BufWriter::new(file), format, fields, every)
+            .map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+    }
+}
+
+impl<W: Write> MetricsSink<W> {
+    /// Start a time series, writing the CSV header straight away
+    pub fn new(mut writer: W, format: MetricsFormat, fields: Vec<Metric>, every: u64) -> std::io::Result<Self> {
+        if format == MetricsFormat::Csv {
+            let header: Vec<&str> = fields.iter().map(|metric| metric.name()).collect();
+            writeln!(writer, "{}", header.join(","))?;
+        }
+        Ok(Self {
+            writer,
+            format,
+            fields,
+            every: every.max(1),
+            next_sample: 0,
+            last_sample: None,
+            last_values: Vec::new(),
+            diversity: DiversityTracker::new(),
+        })
+    }
+
+    /// Write a sample if at least `every` instructions have run since the last one
+    pub fn maybe_sample(&mut self, sim: &Simulator) -> std::io::Result<()> {
+        if sim.stats.total_instructions < self.next_sample {
+            return Ok(());
+        }
+        self.next_sample = (sim.stats.total_instructions / self.every + 1) * self.every;
+        self.sample(sim)
+    }
+
+    /// Write a sample now
+    pub fn sample(&mut self, sim: &Simulator) -> std::io::Result<()> {
+        self.last_sample = Some(sim.stats.total_instructions);
+        let diversity = self.diversity.measure(&sim.genebank, sim.stats.total_instructions);
+        self.last_values = self.fields.iter().map(|&metric| (metric, metric.sample(sim, &diversity))).collect();
+        let values = self.last_values.iter().map(|&(metric, value)| (metric.name(), value));
+        match self.format {
+            MetricsFormat::Csv => {
+                let row: Vec<String> = values.map(|(_, value)| value.to_string()).collect();
+                writeln!(self.writer, "{}", row.join(","))
+            }
+            MetricsFormat::JsonLines => {
+                let row: Vec<String> = values.map(|(name, value)| format!("\"{}\":{}", name, value)).collect();
+                writeln!(self.writer, "{{{}}}", row.join(","))
+            }
+        }
+    }
+
+    /// Record the final state, unless it was just sampled, and flush
+    pub fn finish(&mut self, sim: &Simulator) -> std::io::Result<()> {
+        if self.last_sample != Some(sim.stats.total_instructions) {
+            self.sample(sim)?;
+        }
+        self.writer.flush()
+    }
+
+    /// Values of the latest sample, in field order
+    pub fn last_values(&self) -> &[(Metric, MetricValue)] {
+        &self.last_values
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::SimulationConfig;
+
+    #[test]
+    fn test_csv_and_jsonl_samples() {
+        let mut sim = Simulator::new(SimulationConfig { memory_size: 4096, seed: Some(2), ..Default::default() }).unwrap();
+        sim.initialize_with_ancestor();
+        let fields = parse_fields("instructions,population,median_size").unwrap();
+        assert!(parse_fields("population,iq").is_err());
+
+        let mut csv = MetricsSink::new(Vec::new(), MetricsFormat::Csv, fields.clone(), 1000).unwrap();
+        let mut jsonl = MetricsSink::new(Vec::new(), MetricsFormat::JsonLines, fields, 1000).unwrap();
+        for _ in 0..200 {
+            sim.step();
+            csv.maybe_sample(&sim).unwrap();
+            jsonl.maybe_sample(&sim).unwrap();
+        }
+
+        let csv = String::from_utf8(csv.into_inner()).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "instructions,population,median_size");
+        // One row for the first step, then one per 1000 instructions
+        assert_eq!(lines.len(), 1 + 1 + sim.stats.total_instructions as usize / 1000);
+        assert_eq!(lines[1], "25,1,46.0000");
+
+        let jsonl = String::from_utf8(jsonl.into_inner()).unwrap();
+        for line in jsonl.lines() {
+            let row: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert!(row["population"].as_u64().unwrap() >= 1);
+        }
+        assert_eq!(jsonl.lines().count(), lines.len() - 1);
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(MetricsFormat::from_path(Path::new("run.jsonl")), Ok(MetricsFormat::JsonLines));
+        assert_eq!(MetricsFormat::from_path(Path::new("run.csv")), Ok(MetricsFormat::Csv));
+        assert_eq!(MetricsFormat::from_path(Path::new("run")), Ok(MetricsFormat::Csv));
+        assert!(MetricsFormat::from_path(Path::new("run.json")).is_err());
+    }
+}
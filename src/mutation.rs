@@ -0,0 +1,79 @@
+use crate::instruction::Instruction;
+use crate::isa::Isa;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// The classes of mutation a simulation can apply
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MutationKind {
+    /// Background flip of a random soup cell, at a rate per executed instruction
+    CosmicRay,
+    /// MovII or MovCD writes a random instruction instead of the one it copied
+    CopyError,
+    /// An arithmetic instruction produces a result off by one
+    Flaw,
+    /// A random instruction is inserted into a daughter genome on divide
+    Insertion,
+    /// A random instruction is removed from a daughter genome on divide
+    Deletion,
+    /// A daughter genome takes a tail segment from another organism on divide
+    Crossover,
+}
+
+/// Insert a random instruction at a random position
+pub fn insert_random(genome: &mut Vec<Instruction>, isa: Isa, rng: &mut impl Rng) {
+    let pos = rng.gen_range(0..=genome.len());
+    genome.insert(pos, isa.random(rng));
+}
+
+/// Delete the instruction at a random position; genomes are never emptied
+pub fn delete_random(genome: &mut Vec<Instruction>, rng: &mut impl Rng) -> bool {
+    if genome.len() <= 1 {
+        return false;
+    }
+    let pos = rng.gen_range(0..genome.len());
+    genome.remove(pos);
+    true
+}
+
+/// One-point crossover: everything from a random cut point onwards is taken
+/// from `other`, as far as both genomes reach. The genome keeps its length.
+pub fn crossover(genome: &mut [Instruction], other: &[Instruction], rng: &mut impl Rng) -> bool {
+    let shared = genome.len().min(other.len());
+    if shared < 2 {
+        return false;
+    }
+    let cut = rng.gen_range(1..shared);
+    genome[cut..shared].copy_from_slice(&other[cut..shared]);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use Instruction::*;
+
+    #[test]
+    fn test_genome_level_mutations() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let mut genome = vec![Nop0; 10];
+        insert_random(&mut genome, Isa::TierraRs, &mut rng);
+        assert_eq!(genome.len(), 11);
+        assert!(delete_random(&mut genome, &mut rng));
+        assert_eq!(genome.len(), 10);
+
+        let mut single = vec![Nop0];
+        assert!(!delete_random(&mut single, &mut rng));
+
+        let mut genome = vec![Nop0; 10];
+        let other = vec![Nop1; 6];
+        assert!(crossover(&mut genome, &other, &mut rng));
+        assert_eq!(genome.len(), 10);
+        assert_eq!(genome[0], Nop0, "cut point must keep the genome's head");
+        assert!(genome[1..6].contains(&Nop1));
+        assert!(genome[6..].iter().all(|&inst| inst == Nop0));
+    }
+}
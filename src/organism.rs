@@ -1,7 +1,9 @@
+use crate::genebank::GenotypeId;
 use crate::instruction::Instruction;
+use serde::{Deserialize, Serialize};
 
 /// Represents a living organism in the Tierra simulation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Organism {
     /// Unique identifier
     pub id: usize,
@@ -33,6 +35,13 @@ pub struct Organism {
     /// Parent ID
     pub parent_id: Option<usize>,
 
+    /// Genotype in the simulator's genebank
+    pub genotype: Option<GenotypeId>,
+
+    /// Block from the last MallocA, pending until Divide turns it into the
+    /// offspring. An organism has at most one.
+    pub daughter: Option<Daughter>,
+
     /// Number of CPU cycles executed
     pub cycles: usize,
 
@@ -46,6 +55,21 @@ pub struct Organism {
     pub energy: usize,
 }
 
+/// A block allocated by MallocA that the mother is writing her offspring into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Daughter {
+    pub address: usize,
+    pub size: usize,
+    /// MovII/MovCD writes the mother has made into the block, counting rewrites
+    pub written: usize,
+}
+
+impl Daughter {
+    pub fn new(address: usize, size: usize) -> Self {
+        Self { address, size, written: 0 }
+    }
+}
+
 impl Organism {
     /// Create a new organism
     pub fn new(id: usize, address: usize, size: usize, generation: usize, parent_id: Option<usize>) -> Self {
@@ -62,6 +86,8 @@ impl Organism {
             genome_length: size,
             generation,
             parent_id,
+            genotype: None,
+            daughter: None,
             cycles: 0,
             errors: 0,
             alive: true,
@@ -69,24 +95,23 @@ impl Organism {
         }
     }
 
-    /// Increment the instruction pointer
-    pub fn increment_ip(&mut self) {
-        // Use saturating_sub to prevent overflow if IP is somehow less than address
-        let offset = self.ip.saturating_sub(self.address);
-        self.ip = self.address + ((offset + 1) % self.size);
+    /// Increment the instruction pointer. Within its own block the IP wraps
+    /// from the last instruction back to the first; in foreign code it runs
+    /// on through the soup.
+    pub fn increment_ip(&mut self, memory_size: usize) {
+        if self.is_own_address(self.ip, memory_size) {
+            let offset = (self.ip + memory_size - self.address) % memory_size;
+            self.ip = (self.address + (offset + 1) % self.size) % memory_size;
+        } else {
+            self.ip = (self.ip + 1) % memory_size;
+        }
         self.cycles += 1;
     }
 
-    /// Set the instruction pointer to a new address (for jumps)
-    pub fn set_ip(&mut self, addr: usize) {
-        // Normalize the address to be within the organism's memory bounds
-        if addr >= self.address && addr < self.address + self.size {
-            self.ip = addr;
-        } else {
-            // If out of bounds, wrap it to the organism's memory space
-            let offset = addr % self.size;
-            self.ip = self.address + offset;
-        }
+    /// Set the instruction pointer to a new address (for jumps), which may
+    /// lie outside the organism's own block
+    pub fn set_ip(&mut self, addr: usize, memory_size: usize) {
+        self.ip = addr % memory_size;
     }
 
     /// Push a value onto the stack
@@ -127,9 +152,27 @@ impl Organism {
         }
     }
 
-    /// Check if organism is within its memory bounds
-    pub fn is_address_valid(&self, addr: usize) -> bool {
-        addr >= self.address && addr < self.address + self.size
+    /// Check if an address falls inside the organism's own block, allowing
+    /// for blocks that wrap around the end of memory
+    pub fn is_own_address(&self, addr: usize, memory_size: usize) -> bool {
+        (addr % memory_size + memory_size - self.address % memory_size) % memory_size < self.size
+    }
+
+    /// Check if an address falls inside the daughter block (which may wrap around memory)
+    pub fn is_daughter_address(&self, addr: usize, memory_size: usize) -> bool {
+        match self.daughter {
+            Some(daughter) => (addr % memory_size + memory_size - daughter.address) % memory_size < daughter.size,
+            None => false,
+        }
+    }
+
+    /// Count a MovII or MovCD write at `addr` toward the daughter block if it lands there
+    pub fn record_daughter_write(&mut self, addr: usize, memory_size: usize) {
+        if self.is_daughter_address(addr, memory_size) {
+            if let Some(daughter) = &mut self.daughter {
+                daughter.written += 1;
+            }
+        }
     }
 
     /// Collect a template starting at current IP
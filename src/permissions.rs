@@ -0,0 +1,147 @@
+use crate::organism::Organism;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Kind of memory access an instruction makes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+    Execute,
+}
+
+/// Allowed access kinds for one region
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Access {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+impl Access {
+    pub const ALL: Access = Access { read: true, write: true, execute: true };
+
+    pub fn allows(self, kind: AccessKind) -> bool {
+        match kind {
+            AccessKind::Read => self.read,
+            AccessKind::Write => self.write,
+            AccessKind::Execute => self.execute,
+        }
+    }
+}
+
+/// `rwx` style, with `-` for a denied access
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flag = |allowed, c| if allowed { c } else { '-' };
+        write!(f, "{}{}{}", flag(self.read, 'r'), flag(self.write, 'w'), flag(self.execute, 'x'))
+    }
+}
+
+/// Parses any combination of `r`, `w` and `x`, ignoring `-`
+impl FromStr for Access {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut access = Access { read: false, write: false, execute: false };
+        for c in s.chars() {
+            match c {
+                'r' => access.read = true,
+                'w' => access.write = true,
+                'x' => access.execute = true,
+                '-' => {}
+                _ => return Err(format!("Invalid access flags: {}", s)),
+            }
+        }
+        Ok(access)
+    }
+}
+
+/// Tierra-style memory protection: what an organism may do to its own
+/// block, the daughter block it is writing, and everything else in the soup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Permissions {
+    pub own: Access,
+    pub daughter: Access,
+    pub foreign: Access,
+}
+
+impl Default for Permissions {
+    /// Organisms may read and execute anywhere but only write their own
+    /// and daughter blocks, which is what makes parasites possible
+    fn default() -> Self {
+        Self {
+            own: Access::ALL,
+            daughter: Access::ALL,
+            foreign: Access { read: true, write: false, execute: true },
+        }
+    }
+}
+
+impl Permissions {
+    /// Whether `organism` may make an access of `kind` at `addr`
+    pub fn allows(&self, organism: &Organism, addr: usize, memory_size: usize, kind: AccessKind) -> bool {
+        let region = if organism.is_own_address(addr, memory_size) {
+            self.own
+        } else if organism.is_daughter_address(addr, memory_size) {
+            self.daughter
+        } else {
+            self.foreign
+        };
+        region.allows(kind)
+    }
+}
+
+impl fmt::Display for Permissions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "own={},daughter={},foreign={}", self.own, self.daughter, self.foreign)
+    }
+}
+
+/// Parses `own=rwx,daughter=rw,foreign=rx`; regions left out keep their defaults
+impl FromStr for Permissions {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut permissions = Permissions::default();
+        for part in s.split(',') {
+            let (region, flags) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Expected REGION=FLAGS, got: {}", part))?;
+            let access = flags.trim().parse()?;
+            match region.trim() {
+                "own" => permissions.own = access,
+                "daughter" => permissions.daughter = access,
+                "foreign" => permissions.foreign = access,
+                other => return Err(format!("Unknown memory region: {}", other)),
+            }
+        }
+        Ok(permissions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::organism::Daughter;
+
+    #[test]
+    fn test_regions_and_parsing() {
+        let mut organism = Organism::new(0, 100, 10, 0, None);
+        organism.daughter = Some(Daughter::new(200, 10));
+        let permissions = Permissions::default();
+        assert!(permissions.allows(&organism, 105, 1000, AccessKind::Write));
+        assert!(permissions.allows(&organism, 209, 1000, AccessKind::Write));
+        assert!(!permissions.allows(&organism, 110, 1000, AccessKind::Write));
+        assert!(permissions.allows(&organism, 110, 1000, AccessKind::Execute));
+
+        let strict: Permissions = "foreign=-,daughter=w".parse().unwrap();
+        assert_eq!(strict.to_string(), "own=rwx,daughter=-w-,foreign=---");
+        assert!(!strict.allows(&organism, 500, 1000, AccessKind::Read));
+        assert!(!strict.allows(&organism, 200, 1000, AccessKind::Execute));
+        assert_eq!(strict.to_string().parse::<Permissions>(), Ok(strict));
+        assert!("own=rwz".parse::<Permissions>().is_err());
+        assert!("heap=rw".parse::<Permissions>().is_err());
+    }
+}
@@ -0,0 +1,179 @@
+use crate::genebank::GenotypeId;
+use crate::instruction::Instruction;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Execution counters for one opcode or one genotype
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpcodeCounters {
+    /// Instructions executed
+    pub executions: u64,
+    /// Executions that added to the organism's error count
+    pub errors: u64,
+    /// Template searches made
+    pub searches: u64,
+    /// Searches that found no complement within `max_search`
+    pub failed_searches: u64,
+    /// Summed distance from the template to the complement found, over
+    /// successful searches
+    pub search_distance: u64,
+}
+
+impl OpcodeCounters {
+    /// Mean distance of successful template searches
+    pub fn average_search_distance(&self) -> Option<f64> {
+        let found = self.searches - self.failed_searches;
+        (found > 0).then(|| self.search_distance as f64 / found as f64)
+    }
+
+    fn add(&mut self, other: &OpcodeCounters) {
+        self.executions += other.executions;
+        self.errors += other.errors;
+        self.searches += other.searches;
+        self.failed_searches += other.failed_searches;
+        self.search_distance += other.search_distance;
+    }
+}
+
+/// Per-instruction counters of a profile at one moment
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileSummary {
+    /// Instructions that have been executed, most executed first
+    pub busiest: Vec<(Instruction, OpcodeCounters)>,
+    /// Counters summed over all instructions
+    pub total: OpcodeCounters,
+}
+
+/// Execution profile of the soup: counters per instruction and per genotype
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    /// Indexed by `Instruction::to_u8`
+    by_instruction: Vec<OpcodeCounters>,
+    /// Living genotypes only; counters are dropped on extinction
+    by_genotype: BTreeMap<GenotypeId, OpcodeCounters>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Self {
+            by_instruction: vec![OpcodeCounters::default(); Instruction::COUNT],
+            by_genotype: BTreeMap::new(),
+        }
+    }
+
+    /// Record an organism of `genotype` executing `inst`
+    pub fn record_execution(&mut self, inst: Instruction, genotype: Option<GenotypeId>) {
+        self.update(inst, genotype, |c| c.executions += 1);
+    }
+
+    /// Record an execution of `inst` that failed
+    pub fn record_error(&mut self, inst: Instruction, genotype: Option<GenotypeId>) {
+        self.update(inst, genotype, |c| c.errors += 1);
+    }
+
+    /// Record a template search made by `inst`, with the distance to the
+    /// complement it found, if any
+    pub fn record_search(&mut self, inst: Instruction, genotype: Option<GenotypeId>, distance: Option<usize>) {
+        self.update(inst, genotype, |c| {
+            c.searches += 1;
+            match distance {
+                Some(distance) => c.search_distance += distance as u64,
+                None => c.failed_searches += 1,
+            }
+        });
+    }
+
+    fn update(&mut self, inst: Instruction, genotype: Option<GenotypeId>, f: impl Fn(&mut OpcodeCounters)) {
+        f(&mut self.by_instruction[inst.to_u8() as usize]);
+        if let Some(genotype) = genotype {
+            f(self.by_genotype.entry(genotype).or_default());
+        }
+    }
+
+    /// Counters of one instruction
+    pub fn instruction(&self, inst: Instruction) -> OpcodeCounters {
+        self.by_instruction[inst.to_u8() as usize]
+    }
+
+    /// Counters of everything organisms of `genotype` have executed since
+    /// it last came into existence
+    pub fn genotype(&self, genotype: GenotypeId) -> OpcodeCounters {
+        self.by_genotype.get(&genotype).copied().unwrap_or_default()
+    }
+
+    /// Instructions that have been executed, most executed first
+    pub fn busiest(&self) -> Vec<(Instruction, OpcodeCounters)> {
+        let mut busiest: Vec<_> = Instruction::ALL
+            .into_iter()
+            .map(|inst| (inst, self.instruction(inst)))
+            .filter(|(_, c)| c.executions > 0)
+            .collect();
+        busiest.sort_by_key(|(_, c)| std::cmp::Reverse(c.executions));
+        busiest
+    }
+
+    /// Counters summed over all instructions
+    pub fn total(&self) -> OpcodeCounters {
+        let mut total = OpcodeCounters::default();
+        for counters in &self.by_instruction {
+            total.add(counters);
+        }
+        total
+    }
+
+    /// The per-instruction counters, without the per-genotype ones
+    pub fn summary(&self) -> ProfileSummary {
+        ProfileSummary { busiest: self.busiest(), total: self.total() }
+    }
+
+    /// Drop the counters of a genotype that has gone extinct
+    pub fn forget_genotype(&mut self, genotype: GenotypeId) {
+        self.by_genotype.remove(&genotype);
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_by_instruction_and_genotype() {
+        let mut profile = Profile::new();
+        profile.record_execution(Instruction::JmpB, Some(1));
+        profile.record_search(Instruction::JmpB, Some(1), Some(10));
+        profile.record_execution(Instruction::JmpB, Some(2));
+        profile.record_search(Instruction::JmpB, Some(2), None);
+        profile.record_error(Instruction::JmpB, Some(2));
+        profile.record_execution(Instruction::IncA, None);
+        profile.record_execution(Instruction::IncA, Some(1));
+        profile.record_execution(Instruction::IncA, Some(1));
+
+        let jmpb = profile.instruction(Instruction::JmpB);
+        assert_eq!((jmpb.executions, jmpb.errors, jmpb.searches, jmpb.failed_searches), (2, 1, 2, 1));
+        assert_eq!(jmpb.average_search_distance(), Some(10.0));
+        assert_eq!(profile.instruction(Instruction::IncA).average_search_distance(), None);
+
+        assert_eq!(profile.genotype(1).executions, 3);
+        assert_eq!(profile.genotype(2).failed_searches, 1);
+        assert_eq!(profile.genotype(3), OpcodeCounters::default());
+        assert_eq!(profile.total().executions, 5);
+
+        let busiest: Vec<_> = profile.busiest().into_iter().map(|(inst, _)| inst).collect();
+        assert_eq!(busiest, vec![Instruction::IncA, Instruction::JmpB]);
+        assert_eq!(profile.summary().total, profile.total());
+
+        profile.forget_genotype(1);
+        assert_eq!(profile.genotype(1), OpcodeCounters::default());
+        assert_eq!(profile.instruction(Instruction::IncA).executions, 3);
+    }
+}
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Tierra's reaper queue. Organisms join at the bottom when born and drift
+/// towards the top as younger ones join below them; every error moves an
+/// organism one place up. When memory runs short, the organism at the top
+/// is killed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Reaper {
+    /// Organism IDs, top (next to die) first
+    queue: VecDeque<usize>,
+}
+
+impl Reaper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a newborn organism at the bottom of the queue
+    pub fn add(&mut self, id: usize) {
+        self.queue.push_back(id);
+    }
+
+    /// Remove an organism that died by other means
+    pub fn remove(&mut self, id: usize) {
+        if let Some(pos) = self.position(id) {
+            self.queue.remove(pos);
+        }
+    }
+
+    /// Move an organism one place towards the top after an error
+    pub fn bump(&mut self, id: usize) {
+        if let Some(pos) = self.position(id) {
+            if pos > 0 {
+                self.queue.swap(pos, pos - 1);
+            }
+        }
+    }
+
+    /// The organism that will be reaped next, skipping `exclude`
+    pub fn next_victim(&self, exclude: Option<usize>) -> Option<usize> {
+        self.queue.iter().copied().find(|&id| Some(id) != exclude)
+    }
+
+    /// Position of an organism in the queue, 0 being the top
+    pub fn position(&self, id: usize) -> Option<usize> {
+        self.queue.iter().position(|&queued| queued == id)
+    }
+
+    /// Organism IDs from top to bottom
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.queue.iter().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_orders_by_age_and_errors() {
+        let mut reaper = Reaper::new();
+        for id in 0..4 {
+            reaper.add(id);
+        }
+        assert_eq!(reaper.next_victim(None), Some(0));
+        assert_eq!(reaper.next_victim(Some(0)), Some(1));
+
+        // Errors push an organism past older ones
+        reaper.bump(3);
+        reaper.bump(3);
+        assert_eq!(reaper.iter().collect::<Vec<_>>(), vec![0, 3, 1, 2]);
+        reaper.bump(3);
+        reaper.bump(3);
+        assert_eq!(reaper.next_victim(None), Some(3));
+
+        reaper.remove(3);
+        assert_eq!(reaper.iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(reaper.position(2), Some(2));
+    }
+}
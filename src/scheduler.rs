@@ -1,13 +1,103 @@
 use crate::organism::Organism;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// How many instructions an organism gets per turn
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum SlicePolicy {
+    /// Every organism gets exactly `time_slice` instructions
+    #[default]
+    Fixed,
+    /// Tierra's size-proportional slice: genome size raised to `power`.
+    /// Powers above 1 favour large creatures, below 1 small ones.
+    SizePower { power: f64 },
+    /// `time_slice` scaled by a random factor in `1 ± fraction`
+    Jitter { fraction: f64 },
+}
+
+impl SlicePolicy {
+    /// Short name used in config files, flags and the UI
+    pub fn name(&self) -> &'static str {
+        match self {
+            SlicePolicy::Fixed => "fixed",
+            SlicePolicy::SizePower { .. } => "size",
+            SlicePolicy::Jitter { .. } => "jitter",
+        }
+    }
+
+    /// Check that the policy's parameter is a finite number
+    pub fn check(&self) -> Result<(), String> {
+        match *self {
+            SlicePolicy::SizePower { power: value } | SlicePolicy::Jitter { fraction: value } if !value.is_finite() => {
+                Err(format!("Invalid slice policy parameter: {}", value))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Instructions granted to an organism of `size` for one turn
+    pub fn slice(&self, time_slice: usize, size: usize, rng: &mut impl Rng) -> usize {
+        let slice = match *self {
+            SlicePolicy::Fixed => return time_slice.max(1),
+            SlicePolicy::SizePower { power } => (size as f64).powf(power),
+            SlicePolicy::Jitter { fraction } => {
+                let fraction = fraction.clamp(0.0, 1.0);
+                time_slice as f64 * (1.0 + rng.gen_range(-fraction..=fraction))
+            }
+        };
+        (slice.round() as usize).max(1)
+    }
+}
+
+impl fmt::Display for SlicePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SlicePolicy::Fixed => write!(f, "fixed"),
+            SlicePolicy::SizePower { power } => write!(f, "size:{}", power),
+            SlicePolicy::Jitter { fraction } => write!(f, "jitter:{}", fraction),
+        }
+    }
+}
+
+/// Parses `fixed`, `size[:POWER]` or `jitter[:FRACTION]`
+impl FromStr for SlicePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, arg) = match s.split_once(':') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (s, None),
+        };
+        let number = |default: f64| -> Result<f64, String> {
+            match arg {
+                Some(arg) => arg.parse().map_err(|_| format!("Invalid slice policy parameter: {}", arg)),
+                None => Ok(default),
+            }
+        };
+        let policy = match name {
+            "fixed" if arg.is_none() => SlicePolicy::Fixed,
+            "size" => SlicePolicy::SizePower { power: number(1.0)? },
+            "jitter" => SlicePolicy::Jitter { fraction: number(0.5)? },
+            _ => return Err(format!("Unknown slice policy: {}", s)),
+        };
+        policy.check()?;
+        Ok(policy)
+    }
+}
 
 /// Scheduler for managing CPU time allocation to organisms
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Scheduler {
     /// Current organism index being executed
     pub current_index: usize,
 
     /// Time slice size (instructions per organism per turn)
     pub time_slice: usize,
+
+    /// How the slice is scaled per organism
+    pub policy: SlicePolicy,
 }
 
 impl Scheduler {
@@ -15,41 +105,32 @@ impl Scheduler {
         Self {
             current_index: 0,
             time_slice,
+            policy: SlicePolicy::Fixed,
         }
     }
 
-    /// Select the next organism to execute
+    /// Select the next living organism in round-robin order and grant it
+    /// this turn's slice as energy
     pub fn select_next(&mut self, organisms: &mut [Organism], rng: &mut impl Rng) -> Option<usize> {
         if organisms.is_empty() {
             return None;
         }
 
-        // Simple round-robin scheduling with random start position occasionally
-        if rng.gen::<f64>() < 0.1 {
-            // 10% chance to pick a random organism
-            self.current_index = rng.gen_range(0..organisms.len());
-        }
-
-        // Find next alive organism
-        let start_index = self.current_index;
-        loop {
-            if organisms[self.current_index].alive {
-                let idx = self.current_index;
-                organisms[idx].reset_energy(self.time_slice);
-
+        let start_index = self.current_index % organisms.len();
+        for offset in 0..organisms.len() {
+            let idx = (start_index + offset) % organisms.len();
+            if organisms[idx].alive {
                 // Move to next for next time
-                self.current_index = (self.current_index + 1) % organisms.len();
+                self.current_index = (idx + 1) % organisms.len();
 
+                let slice = self.policy.slice(self.time_slice, organisms[idx].size, rng);
+                organisms[idx].reset_energy(slice);
                 return Some(idx);
             }
-
-            self.current_index = (self.current_index + 1) % organisms.len();
-
-            // If we've checked all organisms, none are alive
-            if self.current_index == start_index {
-                return None;
-            }
         }
+
+        // None are alive
+        None
     }
 
     /// Clean up dead organisms from the population
@@ -65,3 +146,50 @@ impl Default for Scheduler {
         Self::new(25) // Default time slice of 25 instructions
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha12Rng;
+
+    #[test]
+    fn test_slice_policies() {
+        let mut rng = ChaCha12Rng::seed_from_u64(0);
+        assert_eq!(SlicePolicy::Fixed.slice(25, 80, &mut rng), 25);
+        assert_eq!(SlicePolicy::SizePower { power: 1.0 }.slice(25, 80, &mut rng), 80);
+        assert_eq!(SlicePolicy::SizePower { power: 0.5 }.slice(25, 100, &mut rng), 10);
+        for _ in 0..100 {
+            let slice = SlicePolicy::Jitter { fraction: 0.2 }.slice(25, 80, &mut rng);
+            assert!((20..=30).contains(&slice));
+        }
+
+        for policy in [SlicePolicy::Fixed, SlicePolicy::SizePower { power: 1.5 }, SlicePolicy::Jitter { fraction: 0.25 }] {
+            assert_eq!(policy.to_string().parse::<SlicePolicy>(), Ok(policy));
+        }
+        assert_eq!("size".parse::<SlicePolicy>(), Ok(SlicePolicy::SizePower { power: 1.0 }));
+        assert!("fixed:2".parse::<SlicePolicy>().is_err());
+        assert!("lottery".parse::<SlicePolicy>().is_err());
+        assert!("jitter:NaN".parse::<SlicePolicy>().is_err());
+        assert!("size:inf".parse::<SlicePolicy>().is_err());
+    }
+
+    #[test]
+    fn test_select_next_skips_dead_and_grants_slice() {
+        let mut rng = ChaCha12Rng::seed_from_u64(0);
+        let mut organisms: Vec<Organism> = (0..3).map(|i| Organism::new(i, i * 100, 50 + i, 0, None)).collect();
+        organisms[1].kill();
+
+        let mut scheduler = Scheduler::new(10);
+        scheduler.policy = SlicePolicy::SizePower { power: 1.0 };
+        assert_eq!(scheduler.select_next(&mut organisms, &mut rng), Some(0));
+        assert_eq!(organisms[0].energy, 50);
+        assert_eq!(scheduler.select_next(&mut organisms, &mut rng), Some(2));
+        assert_eq!(organisms[2].energy, 52);
+        assert_eq!(scheduler.select_next(&mut organisms, &mut rng), Some(0));
+
+        organisms[0].kill();
+        organisms[2].kill();
+        assert_eq!(scheduler.select_next(&mut organisms, &mut rng), None);
+    }
+}
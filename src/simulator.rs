@@ -1,232 +1,670 @@
+use crate::allocator::Placement;
 use crate::cpu::{CPU, ExecutionResult};
+use crate::genebank::Genebank;
+use crate::lineage::Lineage;
 use crate::instruction::Instruction;
+use crate::interaction::InteractionMatrix;
+use crate::isa::Isa;
 use crate::memory::Memory;
-use crate::organism::Organism;
-use crate::scheduler::Scheduler;
+use crate::mutation::{self, MutationKind};
+use crate::organism::{Daughter, Organism};
+use crate::permissions::Permissions;
+use crate::reaper::Reaper;
+use crate::scheduler::{Scheduler, SlicePolicy};
 use crate::stats::Statistics;
-use rand::rngs::StdRng;
+use crate::topology::Topology;
 use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
 
 /// Configuration for the simulation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SimulationConfig {
     pub memory_size: usize,
+    /// Shape of the address space; changing it takes effect on reset
+    pub topology: Topology,
+    /// Instruction set; changing it takes effect on reset
+    pub isa: Isa,
+    /// Copy error probability per MovII/MovCD write
     pub mutation_rate: f64,
+    /// Probability per executed instruction of flipping a random soup cell
+    pub cosmic_ray_rate: f64,
+    /// Probability that an arithmetic instruction is off by one
+    pub flaw_rate: f64,
+    /// Probability per divide of inserting a random instruction into the daughter
+    pub insertion_rate: f64,
+    /// Probability per divide of deleting an instruction from the daughter
+    pub deletion_rate: f64,
+    /// Probability per divide of crossing the daughter with another organism
+    pub crossover_rate: f64,
+    /// Memory usage fraction above which the reaper kills organisms
+    pub reaper_threshold: f64,
     pub max_population: usize,
     pub time_slice: usize,
+    /// How `time_slice` is scaled per organism
+    pub slice_policy: SlicePolicy,
+    /// Where MallocA places daughter blocks
+    pub placement: Placement,
+    /// Fraction of its daughter block a mother must have written with MovII/MovCD
+    /// before Divide succeeds, like Tierra's `MovPropThrDiv`
+    pub divide_min_written: f64,
+    /// Compact the soup, before reaping, when a MallocA finds no free block
+    /// large enough although enough cells are free. Linear and circular
+    /// soups only.
+    pub compaction: bool,
+    /// Read/write/execute permissions for own, daughter and foreign memory
+    pub permissions: Permissions,
+    /// Seed for every random decision; `None` seeds from system entropy
+    pub seed: Option<u64>,
 }
 
 impl Default for SimulationConfig {
     fn default() -> Self {
         Self {
             memory_size: 65536,
+            topology: Topology::default(),
+            isa: Isa::default(),
             mutation_rate: 0.001,
+            cosmic_ray_rate: 0.0,
+            flaw_rate: 0.0,
+            insertion_rate: 0.0,
+            deletion_rate: 0.0,
+            crossover_rate: 0.0,
+            reaper_threshold: 0.8,
             max_population: 200,
             time_slice: 25,
+            slice_policy: SlicePolicy::Fixed,
+            placement: Placement::default(),
+            divide_min_written: 0.7,
+            compaction: false,
+            permissions: Permissions::default(),
+            seed: None,
         }
     }
 }
 
+impl SimulationConfig {
+    /// Check the settings a simulation cannot run with
+    pub fn validate(&self) -> Result<(), String> {
+        if self.memory_size == 0 {
+            return Err("Memory size must be positive".to_string());
+        }
+        let fractions = [
+            ("Mutation rate", self.mutation_rate),
+            ("Cosmic ray rate", self.cosmic_ray_rate),
+            ("Flaw rate", self.flaw_rate),
+            ("Insertion rate", self.insertion_rate),
+            ("Deletion rate", self.deletion_rate),
+            ("Crossover rate", self.crossover_rate),
+            ("Reaper threshold", self.reaper_threshold),
+            ("Divide written fraction", self.divide_min_written),
+        ];
+        if let Some((name, value)) = fractions.iter().find(|(_, value)| !(0.0..=1.0).contains(value)) {
+            return Err(format!("{} must be between 0 and 1, not {}", name, value));
+        }
+        self.slice_policy.check()?;
+        self.topology.check(self.memory_size)?;
+        if self.compaction && !self.topology.compactable() {
+            return Err(format!("Compaction needs a linear or circular topology, not {}", self.topology.name()));
+        }
+        Ok(())
+    }
+}
+
 /// Main simulation engine
+#[derive(Serialize, Deserialize)]
 pub struct Simulator {
     pub memory: Memory,
     pub organisms: Vec<Organism>,
     pub cpu: CPU,
     pub scheduler: Scheduler,
+    pub reaper: Reaper,
     pub stats: Statistics,
+    pub genebank: Genebank,
+    pub lineage: Lineage,
+    pub interactions: InteractionMatrix,
     pub config: SimulationConfig,
-    pub rng: StdRng,
+    /// ChaCha12 is the algorithm behind `StdRng`, but its state can be serialized
+    pub rng: ChaCha12Rng,
     next_organism_id: usize,
+    /// Instruction count at the last removal of dead organisms
+    last_reap: u64,
+    /// Instruction count at the last statistics update
+    last_stats: u64,
+    #[serde(skip)]
     pub running: bool,
 }
 
 impl Simulator {
-    pub fn new(config: SimulationConfig) -> Self {
-        let memory = Memory::new(config.memory_size);
+    /// Create an empty simulation, or say why `config` cannot be run
+    pub fn new(config: SimulationConfig) -> Result<Self, String> {
+        config.validate()?;
+        let memory = Memory::with_topology(config.memory_size, config.topology);
         let stats = Statistics::new(config.memory_size);
-        let scheduler = Scheduler::new(config.time_slice);
-
-        Self {
+        let mut scheduler = Scheduler::new(config.time_slice);
+        scheduler.policy = config.slice_policy;
+        let mut cpu = CPU::new();
+        cpu.isa = config.isa;
+        let mut genebank = Genebank::new();
+        genebank.isa = config.isa;
+
+        Ok(Self {
             memory,
             organisms: Vec::new(),
-            cpu: CPU::new(),
+            cpu,
             scheduler,
+            reaper: Reaper::new(),
             stats,
+            genebank,
+            lineage: Lineage::new(),
+            interactions: InteractionMatrix::new(),
+            rng: make_rng(config.seed),
             config,
-            rng: StdRng::from_entropy(),
             next_organism_id: 0,
+            last_reap: 0,
+            last_stats: 0,
             running: false,
-        }
+        })
     }
 
     /// Initialize the simulation with the ancestor organism
     pub fn initialize_with_ancestor(&mut self) {
         // The ancestor is a simple self-replicating program
-        let ancestor = create_ancestor();
+        self.initialize_with_genome(&ancestor(self.cpu.isa));
+    }
+
+    /// Seed the soup with one organism running the given genome.
+    /// Returns the new organism's ID, or None if there was no room for it.
+    pub fn initialize_with_genome(&mut self, genome: &[Instruction]) -> Option<usize> {
+        self.inoculate(genome, None, 1).first().copied()
+    }
 
-        // Place it in memory
-        let size = ancestor.len();
-        if let Some(addr) = self.memory.allocate(size, &mut self.rng) {
-            for (i, &inst) in ancestor.iter().enumerate() {
+    /// Inject `count` new organisms running `genome` into the soup, which may
+    /// already be running. With an address hint the copies are packed into
+    /// the first free blocks at or after that address; otherwise each one is
+    /// placed at random. Returns the IDs of the organisms actually placed,
+    /// which is fewer than `count` if the soup ran out of room.
+    pub fn inoculate(&mut self, genome: &[Instruction], address_hint: Option<usize>, count: usize) -> Vec<usize> {
+        let size = genome.len();
+        let mut ids = Vec::with_capacity(count);
+        let mut hint = address_hint;
+
+        for _ in 0..count {
+            // Place it in memory
+            let placed = match hint {
+                Some(addr) => self.memory.allocate(size, Placement::NearMother, addr, &mut self.rng),
+                None => self.memory.allocate(size, Placement::RandomFit, 0, &mut self.rng),
+            };
+            let Some(addr) = placed else {
+                break;
+            };
+            for (i, &inst) in genome.iter().enumerate() {
                 self.memory.write(addr + i, inst);
             }
+            hint = hint.map(|_| addr + size);
 
-            // Memory.allocate() already marked this memory as allocated,
+            // Memory allocation already marked this memory as allocated,
             // so we don't need to call mark_allocated again
 
             // Create the organism
-            let organism = Organism::new(self.next_organism_id, addr, size, 0, None);
+            let id = self.next_organism_id;
+            let mut organism = Organism::new(id, addr, size, 0, None);
+            organism.genotype = Some(self.genebank.record_birth(genome, None, self.stats.total_instructions));
             self.next_organism_id += 1;
+            self.lineage.record_birth(id, None, 0, organism.genotype, size, self.stats.total_instructions);
             self.organisms.push(organism);
+            self.reaper.add(id);
             self.stats.record_birth(size, 0);
+            ids.push(id);
         }
+
+        ids
     }
 
     /// Step the simulation forward by one time slice
     pub fn step(&mut self) {
-        if let Some(organism_idx) = self.find_next_organism() {
-            // Execute time slice for this organism
-            for _ in 0..self.config.time_slice {
+        self.sync_config();
+
+        if let Some(organism_idx) = self.scheduler.select_next(&mut self.organisms, &mut self.rng) {
+            // Execute time slice for this organism; the scheduler granted it as energy
+            loop {
                 let organism = &mut self.organisms[organism_idx];
 
                 if !organism.alive || !organism.consume_energy() {
                     break;
                 }
 
-                let result = self.cpu.execute_instruction(organism, &mut self.memory, &mut self.rng);
-                self.stats.record_instruction();
-
-                match result {
-                    ExecutionResult::Continue => {}
-                    ExecutionResult::Dead => {
-                        let org = &self.organisms[organism_idx];
-                        self.stats.record_death(org.size, org.generation);
-                        self.memory.free(org.address, org.size);
-                        break;
-                    }
-                    ExecutionResult::Malloc(size) => {
-                        // Store the address in BX if successful
-                        if let Some(addr) = self.memory.allocate(size, &mut self.rng) {
-                            self.organisms[organism_idx].bx = addr;
-                        } else {
-                            self.organisms[organism_idx].errors += 1;
-                        }
-                        // Increment IP after malloc (instruction pointer was not advanced in execute_instruction)
-                        self.organisms[organism_idx].increment_ip();
-                    }
-                    ExecutionResult::Divide => {
-                        self.handle_divide(organism_idx);
-                        // Increment IP after divide so the organism doesn't execute Divide again
-                        self.organisms[organism_idx].increment_ip();
-                        break;
-                    }
+                if matches!(self.execute_one(organism_idx), ExecutionResult::Dead | ExecutionResult::Divide) {
+                    break;
                 }
             }
         }
 
-        // Periodically clean up dead organisms
-        if self.stats.total_instructions % 1000 == 0 {
-            let reaped = Scheduler::reap_dead(&mut self.organisms);
-            if reaped > 0 {
-                // Update stats if needed
-            }
+        // Periodically clean up dead organisms. Slices advance the count by
+        // more than one, so compare against the last reap rather than test
+        // for a multiple
+        if self.stats.total_instructions - self.last_reap >= REAP_INTERVAL {
+            Scheduler::reap_dead(&mut self.organisms);
+            self.last_reap = self.stats.total_instructions;
         }
 
         // Update statistics
-        if self.stats.total_instructions % 100 == 0 {
+        if self.stats.total_instructions - self.last_stats >= STATS_INTERVAL {
             self.update_stats();
+            self.last_stats = self.stats.total_instructions;
         }
     }
 
-    /// Handle organism division (reproduction)
-    fn handle_divide(&mut self, parent_idx: usize) {
-        let parent = &self.organisms[parent_idx];
+    /// Keep the CPU and scheduler in sync with live config changes; copy
+    /// errors and flaws happen inside the CPU
+    fn sync_config(&mut self) {
+        self.cpu.copy_mutation_rate = self.config.mutation_rate;
+        self.cpu.flaw_rate = self.config.flaw_rate;
+        self.scheduler.time_slice = self.config.time_slice;
+        self.scheduler.policy = self.config.slice_policy;
+        self.cpu.permissions = self.config.permissions;
+    }
 
-        // Check if population limit reached
-        if self.organisms.len() >= self.config.max_population {
-            self.stats.record_replication(false);
-            return;
+    /// Execute a single instruction of the organism at `organism_idx` and
+    /// carry out whatever it asked for (allocation, division, death)
+    fn execute_one(&mut self, organism_idx: usize) -> ExecutionResult {
+        let organism = &mut self.organisms[organism_idx];
+        let errors = organism.errors;
+        let result = self.cpu.execute_instruction(organism, &mut self.memory, &mut self.rng);
+        if organism.errors > errors {
+            self.reaper.bump(organism.id);
         }
+        if !self.cpu.foreign_code.is_empty() {
+            self.record_interactions(organism_idx);
+        }
+        self.stats.record_instruction();
+        self.maybe_cosmic_ray();
+
+        match result {
+            ExecutionResult::Continue => {}
+            ExecutionResult::Mutated(kind) => self.stats.record_mutation(kind),
+            ExecutionResult::Dead => {
+                self.kill_organism(organism_idx);
+            }
+            ExecutionResult::Malloc(size) => {
+                // A new request replaces any daughter block still being written;
+                // otherwise a malloc loop would leak until the reaper killed everyone
+                if let Some(daughter) = self.organisms[organism_idx].daughter.take() {
+                    self.memory.free(daughter.address, daughter.size);
+                }
+                // Store the address in BX if successful and grant write access to the block.
+                // Impossible requests fail outright rather than sending the reaper after everyone
+                let allocated = if size > 0 && size <= self.max_organism_size() {
+                    self.allocate_with_reaper(size, organism_idx)
+                } else {
+                    None
+                };
+                if let Some(addr) = allocated {
+                    let organism = &mut self.organisms[organism_idx];
+                    organism.bx = addr;
+                    organism.daughter = Some(Daughter::new(addr, size));
+                } else {
+                    let organism = &mut self.organisms[organism_idx];
+                    organism.errors += 1;
+                    self.reaper.bump(organism.id);
+                    self.cpu.profile.record_error(Instruction::MallocA, organism.genotype);
+                }
+                // Increment IP after malloc (instruction pointer was not advanced in execute_instruction)
+                self.organisms[organism_idx].increment_ip(self.memory.size());
+            }
+            ExecutionResult::Divide => {
+                self.handle_divide(organism_idx);
+                // Increment IP after divide so the organism doesn't execute Divide again
+                self.organisms[organism_idx].increment_ip(self.memory.size());
+            }
+        }
+        result
+    }
 
-        // The offspring location is typically in BX register
-        let offspring_addr = parent.bx;
-        let offspring_size = parent.cx; // Size is often in CX
-
-        // Validate offspring
-        if offspring_size == 0 || offspring_size > self.config.memory_size / 10 {
-            self.stats.record_replication(false);
+    /// Record the organism at `user_idx` using code that lies in other
+    /// organisms' blocks, as reported by the CPU
+    fn record_interactions(&mut self, user_idx: usize) {
+        let user = &self.organisms[user_idx];
+        let memory_size = self.memory.size();
+        let Some(user_genotype) = user.genotype else {
             return;
+        };
+        for &addr in &self.cpu.foreign_code {
+            let provider = self.organisms.iter().find(|o| o.alive && o.id != user.id && o.is_own_address(addr, memory_size));
+            if let Some(provider_genotype) = provider.and_then(|o| o.genotype) {
+                self.interactions.record(user_genotype, provider_genotype);
+            }
         }
+    }
 
-        // IMPORTANT: DO NOT call mark_allocated here!
-        // The memory should have already been allocated by MallocA, which called
-        // Memory.allocate(), which already marked the memory as allocated.
-        // Calling mark_allocated here with potentially different size values
-        // corrupts the allocation tracking and causes memory overlaps.
-
-        // Copy genome from parent to offspring location with mutations
-        let parent_addr = parent.address;
-        let parent_size = parent.size;
+    /// Execute one instruction of the living organism with the given ID,
+    /// outside the scheduler. Used by the debugger.
+    pub fn step_organism(&mut self, id: usize) -> Option<ExecutionResult> {
+        self.sync_config();
+        let idx = self.organisms.iter().position(|o| o.id == id && o.alive)?;
+        Some(self.execute_one(idx))
+    }
 
-        for i in 0..parent_size.min(offspring_size) {
-            let inst = self.memory.read(parent_addr + i);
-            self.memory.write(offspring_addr + i, inst);
+    /// Handle organism division (reproduction)
+    fn handle_divide(&mut self, parent_idx: usize) {
+        // The offspring is whatever the parent wrote into its pending daughter
+        // block, which stays pending if it has not written enough of it yet
+        let parent = &self.organisms[parent_idx];
+        let parent_id = parent.id;
+        let daughter = match parent.daughter {
+            Some(daughter) if daughter.written as f64 >= daughter.size as f64 * self.config.divide_min_written => daughter,
+            _ => {
+                self.fail_divide(parent_idx);
+                return;
+            }
+        };
 
-            // Apply mutations
-            if self.rng.gen::<f64>() < self.config.mutation_rate {
-                self.memory.maybe_mutate(offspring_addr + i, 1.0, &mut self.rng);
-                self.stats.record_mutation();
+        // Make room for the offspring once the population limit is reached
+        while self.organisms.iter().filter(|o| o.alive).count() >= self.config.max_population {
+            if !self.reap(parent_id) {
+                self.stats.record_replication(false);
+                return;
             }
         }
 
         // Create new organism
-        let parent_id = parent.id;
+        let parent = &self.organisms[parent_idx];
+        let (offspring_addr, offspring_size) = (daughter.address, daughter.size);
         let parent_generation = parent.generation;
+        let parent_genotype = parent.genotype;
+
+        let offspring_size = self.mutate_daughter(parent_idx, offspring_addr, offspring_size);
+
+        let genome = self.memory.get_slice(offspring_addr, offspring_size);
+        let genotype = self.genebank.record_birth(&genome, parent_genotype, self.stats.total_instructions);
 
-        let offspring = Organism::new(
+        let mut offspring = Organism::new(
             self.next_organism_id,
             offspring_addr,
             offspring_size,
             parent_generation + 1,
             Some(parent_id),
         );
+        offspring.genotype = Some(genotype);
+        self.organisms[parent_idx].daughter = None;
 
+        self.reaper.add(offspring.id);
+        self.lineage.record_birth(
+            offspring.id,
+            Some(parent_id),
+            offspring.generation,
+            offspring.genotype,
+            offspring_size,
+            self.stats.total_instructions,
+        );
         self.next_organism_id += 1;
         self.organisms.push(offspring);
         self.stats.record_birth(offspring_size, parent_generation + 1);
         self.stats.record_replication(true);
     }
 
-    /// Find the next organism to execute
-    fn find_next_organism(&mut self) -> Option<usize> {
-        if self.organisms.is_empty() {
-            return None;
+    /// Count a Divide without a (sufficiently written) daughter as an error
+    fn fail_divide(&mut self, parent_idx: usize) {
+        let parent = &mut self.organisms[parent_idx];
+        parent.errors += 1;
+        self.reaper.bump(parent.id);
+        self.cpu.profile.record_error(Instruction::Divide, parent.genotype);
+        self.stats.record_replication(false);
+    }
+
+    /// Allocate a daughter block for the organism at `requester_idx`. The
+    /// reaper first kills organisms while memory usage would pass
+    /// `reaper_threshold`, then keeps killing until the request fits.
+    fn allocate_with_reaper(&mut self, size: usize, requester_idx: usize) -> Option<usize> {
+        let requester = self.organisms[requester_idx].id;
+        let limit = (self.memory.size() as f64 * self.config.reaper_threshold) as usize;
+        while self.memory.count_used_cells() + size > limit {
+            if !self.reap(requester) {
+                break;
+            }
+        }
+
+        if let Some(starts) = self.neighbourhood(size, requester_idx) {
+            return self.allocate_in_neighbourhood(size, requester_idx, &starts);
+        }
+
+        let mut compacted = false;
+        loop {
+            // Compaction moves the mother, so look her up each time
+            let mother = &self.organisms[requester_idx];
+            let near = mother.address + mother.size;
+            if let Some(addr) = self.memory.allocate(size, self.config.placement, near, &mut self.rng) {
+                return Some(addr);
+            }
+            if self.config.compaction && !compacted && self.memory.count_free_cells() >= size {
+                self.compact();
+                compacted = true;
+                continue;
+            }
+            if !self.reap(requester) {
+                return None;
+            }
+        }
+    }
+
+    /// Where the daughter of the organism at `requester_idx` may start, when
+    /// the topology limits it to a neighbourhood. On islands she is sent to
+    /// another island with probability `migration_rate`.
+    fn neighbourhood(&mut self, size: usize, requester_idx: usize) -> Option<Vec<Range<usize>>> {
+        let memory_size = self.memory.size();
+        let topology = self.memory.topology();
+        let mut home = self.organisms[requester_idx].address;
+        if let Topology::Islands { count, migration_rate } = topology {
+            if count > 1 && self.rng.gen_bool(migration_rate) {
+                let own = topology.island_of(home, memory_size);
+                let other = (own + self.rng.gen_range(1..count)) % count;
+                home = topology.island(other, memory_size).start;
+                self.stats.record_migration();
+            }
+        }
+        topology.placement_starts(home, size, memory_size)
+    }
+
+    /// Allocate a block starting within `starts`, reaping organisms whose
+    /// blocks lie in the neighbourhood until it fits. Organisms elsewhere are
+    /// spared, since their deaths would not make room.
+    fn allocate_in_neighbourhood(&mut self, size: usize, requester_idx: usize, starts: &[Range<usize>]) -> Option<usize> {
+        let requester = self.organisms[requester_idx].id;
+        let memory_size = self.memory.size();
+        // Cells a block starting in the neighbourhood could cover
+        let cells: Vec<(usize, usize)> = starts.iter().map(|range| (range.start, range.len() + size - 1)).collect();
+        let overlaps = |(a, a_len): (usize, usize), (b, b_len): (usize, usize)| {
+            (b + memory_size - a) % memory_size < a_len || (a + memory_size - b) % memory_size < b_len
+        };
+        loop {
+            let mother = &self.organisms[requester_idx];
+            let near = mother.address + mother.size;
+            if let Some(addr) = self.memory.allocate_in(size, self.config.placement, near, starts, &mut self.rng) {
+                return Some(addr);
+            }
+            let victim = self.reaper.iter().filter(|&id| id != requester).find(|&id| {
+                self.organisms.iter().any(|o| {
+                    o.id == id
+                        && o.alive
+                        && std::iter::once((o.address, o.size))
+                            .chain(o.daughter.map(|d| (d.address, d.size)))
+                            .any(|block| cells.iter().any(|&region| overlaps(block, region)))
+                })
+            })?;
+            let idx = self.organisms.iter().position(|o| o.id == victim).expect("victim is alive");
+            self.kill_organism(idx);
+            self.stats.record_reap();
+        }
+    }
+
+    /// Slide every living organism's block and daughter block down to the
+    /// start of the soup, leaving all free memory in one block at the end.
+    /// Each value in an organism's IP, AX, BX and stack that points into a
+    /// block moves with that block, where one cell past the end of its own
+    /// or its daughter block counts as pointing into it, as `AdrF` and the
+    /// copy loop leave them. A number that merely looks like such an address
+    /// is moved too. Returns the number of blocks that moved. Does nothing
+    /// on a torus or islands, where it would carry organisms out of their
+    /// neighbourhoods.
+    pub fn compact(&mut self) -> usize {
+        if !self.memory.topology().compactable() {
+            return 0;
+        }
+        let memory_size = self.memory.size();
+        let mut blocks = Vec::new();
+        for organism in self.organisms.iter().filter(|o| o.alive) {
+            blocks.push((organism.address, organism.size));
+            blocks.extend(organism.daughter.map(|d| (d.address, d.size)));
+        }
+        let starts = self.memory.compact(&blocks);
+        let moves: Vec<Move> = blocks.iter().zip(starts).map(|(&(from, len), to)| Move { from, len, to }).collect();
+
+        let mut next = 0;
+        for organism in self.organisms.iter_mut().filter(|o| o.alive) {
+            let own = &moves[next..next + 1 + organism.daughter.is_some() as usize];
+            next += own.len();
+            let relocate = |value: usize| {
+                own.iter()
+                    .find_map(|m| m.relocate(value, 1, memory_size))
+                    .or_else(|| moves.iter().find_map(|m| m.relocate(value, 0, memory_size)))
+                    .unwrap_or(value)
+            };
+            organism.ip = relocate(organism.ip);
+            organism.ax = relocate(organism.ax);
+            organism.bx = relocate(organism.bx);
+            for value in &mut organism.stack {
+                *value = relocate(*value);
+            }
+            organism.address = own[0].to;
+            if let (Some(daughter), Some(m)) = (&mut organism.daughter, own.get(1)) {
+                daughter.address = m.to;
+            }
+        }
+
+        self.stats.record_compaction();
+        moves.iter().filter(|m| m.from % memory_size != m.to).count()
+    }
+
+    /// Kill the organism at the top of the reaper queue, sparing `spare`.
+    /// Returns false if there was nobody to kill.
+    fn reap(&mut self, spare: usize) -> bool {
+        let Some(victim) = self.reaper.next_victim(Some(spare)) else {
+            return false;
+        };
+        match self.organisms.iter().position(|o| o.id == victim && o.alive) {
+            Some(idx) => {
+                self.kill_organism(idx);
+                self.stats.record_reap();
+            }
+            None => self.reaper.remove(victim),
+        }
+        true
+    }
+
+    /// Kill an organism, freeing its block and any daughter block it was
+    /// still writing, and record the death
+    fn kill_organism(&mut self, idx: usize) {
+        let org = &mut self.organisms[idx];
+        org.kill();
+        let (id, address, size, generation) = (org.id, org.address, org.size, org.generation);
+        let genotype = org.genotype;
+        let daughter = org.daughter.take();
+
+        self.stats.record_death(size, generation);
+        if let Some(genotype) = genotype {
+            self.genebank.record_death(genotype);
+            if self.genebank.get(genotype).is_some_and(|g| !g.is_alive()) {
+                self.cpu.profile.forget_genotype(genotype);
+            }
+        }
+        self.memory.free(address, size);
+        if let Some(daughter) = daughter {
+            self.memory.free(daughter.address, daughter.size);
+        }
+        self.reaper.remove(id);
+        self.lineage.record_death(id, self.stats.total_instructions);
+    }
+
+    /// Flip a random soup cell with probability `cosmic_ray_rate`
+    fn maybe_cosmic_ray(&mut self) {
+        if self.config.cosmic_ray_rate > 0.0 && self.rng.gen::<f64>() < self.config.cosmic_ray_rate {
+            let addr = self.rng.gen_range(0..self.memory.size());
+            if self.memory.maybe_mutate(addr, 1.0, self.cpu.isa, &mut self.rng) {
+                self.stats.record_mutation(MutationKind::CosmicRay);
+            }
         }
+    }
 
-        // Use scheduler to select next organism
-        let current_idx = self.scheduler.current_index % self.organisms.len();
+    /// Apply genome-level mutations (crossover, insertion, deletion) to a
+    /// daughter block on divide. Returns the daughter's new size.
+    fn mutate_daughter(&mut self, parent_idx: usize, addr: usize, size: usize) -> usize {
+        let config = &self.config;
+        let crossover = config.crossover_rate > 0.0 && self.rng.gen::<f64>() < config.crossover_rate;
+        let insertion = config.insertion_rate > 0.0 && self.rng.gen::<f64>() < config.insertion_rate;
+        let deletion = config.deletion_rate > 0.0 && self.rng.gen::<f64>() < config.deletion_rate;
+        if !(crossover || insertion || deletion) {
+            return size;
+        }
 
-        // Find next alive organism
-        for offset in 0..self.organisms.len() {
-            let idx = (current_idx + offset) % self.organisms.len();
-            if self.organisms[idx].alive {
-                self.scheduler.current_index = (idx + 1) % self.organisms.len();
-                // Reset energy for the new time slice
-                self.organisms[idx].reset_energy(self.config.time_slice);
-                return Some(idx);
+        let mut genome = self.memory.get_slice(addr, size);
+
+        if crossover {
+            let partners: Vec<usize> = (0..self.organisms.len())
+                .filter(|&i| i != parent_idx && self.organisms[i].alive)
+                .collect();
+            if !partners.is_empty() {
+                let partner = &self.organisms[partners[self.rng.gen_range(0..partners.len())]];
+                let other = self.memory.get_slice(partner.address, partner.size);
+                if mutation::crossover(&mut genome, &other, &mut self.rng) {
+                    self.stats.record_mutation(MutationKind::Crossover);
+                }
             }
         }
 
-        None
+        // An insertion needs the cell just past the block, wrapping round the
+        // soup; skip it if that cell is taken or the topology ends the block there
+        let next = self.memory.normalize_addr(addr + genome.len());
+        if insertion
+            && genome.len() < self.max_organism_size()
+            && self.memory.topology().allows_block(addr, genome.len() + 1, self.memory.size())
+            && self.memory.allocate_at(next, 1)
+        {
+            mutation::insert_random(&mut genome, self.cpu.isa, &mut self.rng);
+            self.stats.record_mutation(MutationKind::Insertion);
+        }
+
+        if deletion && mutation::delete_random(&mut genome, &mut self.rng) {
+            self.memory.free(self.memory.normalize_addr(addr + genome.len()), 1);
+            self.stats.record_mutation(MutationKind::Deletion);
+        }
+
+        for (i, &inst) in genome.iter().enumerate() {
+            self.memory.write(addr + i, inst);
+        }
+        genome.len()
+    }
+
+    /// Largest block MallocA may request
+    fn max_organism_size(&self) -> usize {
+        self.config.memory_size / 10
     }
 
     /// Update statistics
     fn update_stats(&mut self) {
         let alive_count = self.organisms.iter().filter(|o| o.alive).count();
-        let memory_used = self.memory.size() - self.memory.count_free_cells();
+        let memory_used = self.memory.count_used_cells();
 
         self.stats.update_memory_usage(memory_used);
+        self.stats.update_fragmentation(self.memory.largest_free_block(), self.memory.free_block_count());
         self.stats.update_history(alive_count);
+        self.stats.profile = self.cpu.profile.summary();
+
+        let now = self.stats.total_instructions;
+        if now - self.stats.diversity_tracker.last_measured >= DIVERSITY_INTERVAL {
+            self.stats.diversity = self.stats.diversity_tracker.measure(&self.genebank, now);
+        }
     }
 
     /// Run multiple simulation steps
@@ -236,87 +674,154 @@ impl Simulator {
         }
     }
 
-    /// Reset the simulation
-    pub fn reset(&mut self) {
-        self.memory = Memory::new(self.config.memory_size);
+    /// Reset the simulation, applying config changes that only take effect
+    /// on reset. An invalid config leaves the simulation untouched.
+    pub fn reset(&mut self) -> Result<(), String> {
+        self.config.validate()?;
+        self.memory = Memory::with_topology(self.config.memory_size, self.config.topology);
         self.organisms.clear();
         self.stats = Statistics::new(self.config.memory_size);
+        self.cpu.isa = self.config.isa;
+        self.cpu.profile.clear();
+        self.genebank = Genebank::new();
+        self.genebank.isa = self.config.isa;
+        self.lineage.clear();
+        self.interactions.clear();
+        self.scheduler = Scheduler::new(self.config.time_slice);
+        self.scheduler.policy = self.config.slice_policy;
+        self.reaper.clear();
+        self.rng = make_rng(self.config.seed);
         self.next_organism_id = 0;
+        self.last_reap = 0;
+        self.last_stats = 0;
         self.running = false;
+        Ok(())
     }
 }
 
-/// Create the ancestor organism - a simple self-replicating program
-/// This is a minimal version that just allocates memory and divides
-fn create_ancestor() -> Vec<Instruction> {
-    use Instruction::*;
+/// A block moved by `Simulator::compact`
+struct Move {
+    from: usize,
+    len: usize,
+    to: usize,
+}
+
+impl Move {
+    /// New address of `addr` if it lies in the block or within `slack`
+    /// cells past its end
+    fn relocate(&self, addr: usize, slack: usize, memory_size: usize) -> Option<usize> {
+        let offset = (addr % memory_size + memory_size - self.from % memory_size) % memory_size;
+        (offset < self.len + slack).then(|| (self.to + offset) % memory_size)
+    }
+}
 
-    // Create a simple ancestor that:
-    // 1. Sets AX to organism size (for MallocA)
-    // 2. Calls MallocA (stores offspring address in BX)
-    // 3. Sets CX to organism size (for Divide)
-    // 4. Calls Divide
-    // handle_divide will copy the parent to offspring location
+/// Instructions between the diversity measurements shown in `Statistics`
+const DIVERSITY_INTERVAL: u64 = 10_000;
 
-    // The challenge: we need AX = CX = total_size, but the total size
-    // includes the IncA instructions used to set AX!
-    //
-    // Solution: Build iteratively until AX matches the total size
-    // Structure: 4 (start) + N (IncA) + 1 (MallocA) + 2 (PushA/PopC) + 1 (Divide) + 4 (end)
-    // Total = 12 + N
-    // We need N = 12 + N, which is impossible!
-    //
-    // Better solution: Use address arithmetic to calculate size at runtime
-    // But without subtraction, this is complex.
-    //
-    // Simplest solution: Accept that the organism copies a smaller version
-    // Let's make a 64-byte organism where:
-    // - It uses 64 IncA instructions to set AX=64
-    // - Total size becomes 12 + 64 = 76
-    // - But it allocates and copies only 64 bytes
-    // - So offspring will be 64 bytes (missing the base instructions)
-    //
-    // Actually, let's use a fixed approach where we calculate exactly:
-
-    // Build the instruction list first to know the base size
-    let _base_instructions = vec![
-        Nop1, Nop1, Nop1, Nop1,  // 4 start markers
-        MallocA,                   // 1 malloc
-        PushA,                     // 1 push
-        PopC,                      // 1 pop
-        Divide,                    // 1 divide
-        Nop0, Nop0, Nop0, Nop0,  // 4 end markers
-    ];
-    let _base_size = _base_instructions.len(); // 12
-
-    // We want the total size to be S. With N IncA instructions:
-    // S = base_size + N = 12 + N
-    // After N IncAs, AX = N, CX = N
-    // We need both to equal S, but that means N = 12 + N (impossible!)
-    //
-    // Solution: Accept that offspring will be smaller than parents.
-    // This is a fundamental property of simple self-replicating programs.
-    // With 80 IncAs, the total size will be 92, so offspring allocate 80 bytes
-    // and copy 80 bytes, making them 80 bytes (smaller than the parent's 92).
-    //
-    // This is acceptable and actually mirrors behavior in the original Tierra.
+/// Instructions between removals of dead organisms from `organisms`
+const REAP_INTERVAL: u64 = 1000;
 
-    let num_inc_a = 80;
+/// Instructions between statistics updates
+const STATS_INTERVAL: u64 = 100;
 
-    let mut instructions = vec![];
-    instructions.extend_from_slice(&[Nop1, Nop1, Nop1, Nop1]);
+/// Build the simulation RNG, seeded deterministically when a seed is given
+fn make_rng(seed: Option<u64>) -> ChaCha12Rng {
+    match seed {
+        Some(seed) => ChaCha12Rng::seed_from_u64(seed),
+        None => ChaCha12Rng::from_entropy(),
+    }
+}
 
-    for _ in 0..num_inc_a {
-        instructions.push(IncA);
+/// The built-in ancestor written in the given instruction set
+pub fn ancestor(isa: Isa) -> Vec<Instruction> {
+    match isa {
+        Isa::TierraRs => create_ancestor(),
+        Isa::Tierra0 => create_tierra0_ancestor(),
     }
+}
 
-    instructions.push(MallocA);
-    instructions.push(PushA);
-    instructions.push(PopC);
-    instructions.push(Divide);
-    instructions.extend_from_slice(&[Nop0, Nop0, Nop0, Nop0]);
+/// Create the ancestor organism - a self-replicating program with a real copy loop
+fn create_ancestor() -> Vec<Instruction> {
+    use Instruction::*;
 
-    instructions
+    // The ancestor works in three phases:
+    // 1. Measure its own size: AdrB/AdrF find the addresses just past its
+    //    start and end templates, and subtraction turns them into the start
+    //    address and the length
+    // 2. MallocA a daughter block (address in BX) and copy itself into it
+    //    one instruction at a time with MovII
+    // 3. Divide, which splits the daughter block off as a new organism
+    //
+    // Templates: a search lands just after the nearest complement of its
+    // template, and read_template consumes every consecutive nop, so each
+    // label is preceded by a non-nop. The exit JmpF targets the tail of the
+    // following JmpB's template, landing on the instruction after the loop.
+
+    vec![
+        Nop1, Nop1, Nop1, Nop1,        // start template
+        Zero,
+        Not0,
+        Shl,
+        Shl,                           // CX = 4, the template length
+        AdrB, Nop0, Nop0, Nop0, Nop0,  // AX = just past the start template
+        SubAC,                         // AX = start
+        MovBA,                         // BX = start
+        AdrF, Nop0, Nop0, Nop0, Nop1,  // AX = just past the end template
+        SubAB,                         // CX = size
+        PushB,
+        PushC,
+        PopA,                          // AX = size
+        MallocA,                       // BX = daughter address
+        PopA,                          // AX = start
+
+        // Copy loop: [BX] = [AX], CX times
+        Nop1, Nop0, Nop1,
+        MovII,
+        IncA,
+        IncB,
+        DecC,
+        IfCZ,
+        JmpF, Nop0, Nop1,              // exit: lands after the "1 0" below
+        JmpB, Nop0, Nop1, Nop0,        // loop back to "1 0 1"
+        Divide,
+        Nop1, Nop1, Nop1, Nop0,        // end template; execution then wraps to the start
+    ]
+}
+
+/// Create the ancestor for Tierra's instruction set 0: the same program as
+/// `create_ancestor`, with JmpO standing in for JmpF, which set 0 lacks
+fn create_tierra0_ancestor() -> Vec<Instruction> {
+    use Instruction::*;
+
+    vec![
+        Nop1, Nop1, Nop1, Nop1,  // start template
+        Zero,
+        Not0,
+        Shl,
+        Shl,                     // CX = 4, the template length
+        AdrB, Nop0, Nop0, Nop0, Nop0,  // AX = just past the start template
+        SubAC,                   // AX = start
+        MovBA,                   // BX = start
+        AdrF, Nop0, Nop0, Nop0, Nop1,  // AX = just past the end template
+        SubAB,                   // CX = size
+        PushB,
+        PushC,
+        PopA,                    // AX = size
+        MallocA,                 // BX = daughter address
+        PopA,                    // AX = start
+
+        // Copy loop: [BX] = [AX], CX times
+        Nop1, Nop0, Nop1,
+        MovII,
+        IncA,
+        IncB,
+        DecC,
+        IfCZ,
+        JmpO, Nop0, Nop1,        // exit: the nearest "1 0" is the tail of the template below
+        JmpB, Nop0, Nop1, Nop0,  // loop back to "1 0 1"
+        Divide,
+        Nop1, Nop1, Nop1, Nop0,  // end template; execution then wraps to the start
+    ]
 }
 
 #[cfg(test)]
@@ -330,9 +835,10 @@ mod tests {
             mutation_rate: 0.0, // No mutations for testing
             max_population: 200,
             time_slice: 25,
+            ..Default::default()
         };
 
-        let mut sim = Simulator::new(config);
+        let mut sim = Simulator::new(config).unwrap();
         sim.initialize_with_ancestor();
 
         println!("Starting test...");
@@ -386,10 +892,11 @@ mod tests {
             mutation_rate: 0.0,
             max_population: 200,
             time_slice: 25,
+            ..Default::default()
         };
 
         let memory_size = config.memory_size;
-        let mut sim = Simulator::new(config);
+        let mut sim = Simulator::new(config).unwrap();
         sim.initialize_with_ancestor();
 
         // Run until we get a division
@@ -429,45 +936,49 @@ mod tests {
 
     #[test]
     fn test_ancestor_allocation_matches_genome_size() {
-        // This test verifies that the ancestor organism's AX and CX registers
-        // are set to reasonable values for replication
-        let ancestor = create_ancestor();
-        let ancestor_size = ancestor.len();
-
-        println!("Ancestor genome size: {}", ancestor_size);
-
-        // Count the number of IncA instructions to determine AX value
-        let inc_a_count = ancestor.iter()
-            .filter(|&&inst| inst == Instruction::IncA)
-            .count();
-
-        println!("Number of IncA instructions: {}", inc_a_count);
-        println!("Expected AX after execution: {}", inc_a_count);
+        // The measured size must be exactly the genome length, otherwise the
+        // copy would be truncated or run past the end of the parent
+        for isa in Isa::ALL {
+            let mut sim = Simulator::new(SimulationConfig { memory_size: 4096, isa, seed: Some(1), ..Default::default() }).unwrap();
+            sim.initialize_with_ancestor();
+            let start = sim.organisms[0].address;
+            while sim.memory.read(sim.organisms[0].ip) != Instruction::MallocA {
+                sim.step_organism(0);
+            }
+            let organism = &sim.organisms[0];
+            assert_eq!(organism.ax, ancestor(isa).len(), "{} ancestor measured the wrong size", isa);
+            assert_eq!(organism.stack, vec![start]);
+        }
+    }
 
-        // In Tierra, it's impossible to make AX exactly equal to the genome size
-        // using only IncA instructions, because the genome size includes those IncAs!
-        // So we accept that offspring will be slightly smaller than parents.
-        //
-        // The key safety requirement is that the allocated size (AX) must be:
-        // 1. Large enough to hold a viable organism (at least base size)
-        // 2. Not cause memory corruption by being wildly different from genome size
+    #[test]
+    fn test_ancestor_copies_itself_exactly() {
+        let config = SimulationConfig {
+            memory_size: 8192,
+            mutation_rate: 0.0,
+            max_population: 10,
+            seed: Some(1),
+            ..Default::default()
+        };
 
-        let base_size = 12; // 4 + 1 + 2 + 1 + 4
-        assert!(inc_a_count >= base_size,
-            "AX value {} is too small to create viable offspring", inc_a_count);
+        let mut sim = Simulator::new(config).unwrap();
+        sim.initialize_with_ancestor();
+        let ancestor = create_ancestor();
 
-        // Allow offspring to be smaller, but not by more than 20%
-        let size_diff = ancestor_size.abs_diff(inc_a_count);
-        let max_diff = ancestor_size / 5; // 20%
-        assert!(size_diff <= max_diff,
-            "Ancestor size {} and allocation {} differ by more than 20%: diff = {}",
-            ancestor_size, inc_a_count, size_diff);
+        for _ in 0..5000 {
+            sim.step();
+            if sim.organisms.len() >= 3 {
+                break;
+            }
+        }
+        assert!(sim.organisms.len() >= 3, "Ancestor did not replicate");
 
-        println!("✓ Ancestor allocation is reasonable");
-        println!("  Genome size: {}", ancestor_size);
-        println!("  Allocated size: {}", inc_a_count);
-        println!("  Difference: {} bytes ({:.1}%)",
-            size_diff, (size_diff as f64 / ancestor_size as f64) * 100.0);
+        // Offspring are exact copies, and they replicate in turn
+        for org in &sim.organisms {
+            assert_eq!(sim.memory.get_slice(org.address, org.size), ancestor);
+        }
+        assert!(sim.organisms.iter().any(|o| o.generation >= 1));
+        assert_eq!(sim.genebank.len(), 1, "Mutation-free copies produced a new genotype");
     }
 
     #[test]
@@ -479,11 +990,12 @@ mod tests {
             mutation_rate: 0.0,
             max_population: 20,  // Keep it small for testing
             time_slice: 25,
+            ..Default::default()
         };
 
         let memory_size = config.memory_size;
         let max_population = config.max_population;
-        let mut sim = Simulator::new(config);
+        let mut sim = Simulator::new(config).unwrap();
         sim.initialize_with_ancestor();
 
         let ancestor_size = sim.organisms[0].size;
@@ -558,85 +1070,657 @@ mod tests {
         // Test that memory allocation tracking stays consistent
         let config = SimulationConfig {
             memory_size: 4096,  // Larger to avoid filling up
-            mutation_rate: 0.0,
+            mutation_rate: 0.01, // Mutants that re-malloc or divide early
             max_population: 5,  // Small population
             time_slice: 25,
+            seed: Some(8),
+            ..Default::default()
         };
 
         let memory_size = config.memory_size;
-        let mut sim = Simulator::new(config);
+        let mut sim = Simulator::new(config).unwrap();
         sim.initialize_with_ancestor();
 
-        // Run for several divisions
-        for _ in 0..2000 {
-            sim.step();
-
-            if sim.organisms.len() >= 3 {
-                break;
-            }
-        }
+        // Run through many births and deaths
+        sim.run_steps(2000);
 
-        // Verify memory accounting
+        // Every allocated cell belongs to a living organism or its pending
+        // daughter, and no two of them overlap
         let alive_organisms: Vec<_> = sim.organisms.iter()
             .filter(|o| o.alive)
             .collect();
+        let mut owner = vec![None; memory_size];
+        for org in &alive_organisms {
+            let daughter = org.daughter.map(|d| (d.address, d.size));
+            for (addr, size) in std::iter::once((org.address, org.size)).chain(daughter) {
+                for i in 0..size {
+                    let cell = &mut owner[(addr + i) % memory_size];
+                    assert_eq!(*cell, None, "Organism {} overlaps organism {:?}", org.id, cell);
+                    *cell = Some(org.id);
+                }
+            }
+        }
+        let owned_cells = owner.iter().filter(|cell| cell.is_some()).count();
+        assert_eq!(sim.memory.count_used_cells(), owned_cells,
+            "Allocated cells leaked: {} allocated, {} owned", sim.memory.count_used_cells(), owned_cells);
+        assert_eq!(sim.memory.count_used_cells() + sim.memory.count_free_cells(), memory_size);
+        assert!(sim.stats.reaper_kills > 0, "The population limit never forced a death");
 
-        let total_organism_size: usize = alive_organisms.iter()
-            .map(|o| o.size)
-            .sum();
-
-        let free_cells = sim.memory.count_free_cells();
-        let used_cells = memory_size - free_cells;
-
-        println!("Memory usage:");
-        println!("  Alive organisms: {}", alive_organisms.len());
-        println!("  Total organism size: {}", total_organism_size);
-        println!("  Used cells (tracked): {}", used_cells);
-        println!("  Free cells: {}", free_cells);
-        println!("  Total memory: {}", memory_size);
-        println!("  Organism details:");
-        for (i, org) in alive_organisms.iter().enumerate() {
-            println!("    [{}] addr={}, size={}", i, org.address, org.size);
-        }
-
-        // Check for overlapping organisms (critical bug)
-        for i in 0..alive_organisms.len() {
-            for j in (i + 1)..alive_organisms.len() {
-                let org1 = alive_organisms[i];
-                let org2 = alive_organisms[j];
-                let org1_end = org1.address + org1.size;
-                let org2_end = org2.address + org2.size;
-
-                // Check if they overlap
-                if org1.address < org2_end && org2.address < org1_end {
-                    panic!("CRITICAL BUG: Organisms {} and {} overlap!\n  Org {}: [{}, {})\n  Org {}: [{}, {})",
-                        i, j,
-                        i, org1.address, org1_end,
-                        j, org2.address, org2_end);
+        println!("✓ Memory tracking integrity check passed");
+    }
+
+    #[test]
+    fn test_every_placement_keeps_blocks_disjoint() {
+        for placement in Placement::ALL {
+            let mut sim = Simulator::new(SimulationConfig {
+                memory_size: 4096,
+                placement,
+                max_population: 60,
+                seed: Some(9),
+                ..Default::default()
+            }).unwrap();
+            sim.initialize_with_ancestor();
+            sim.run_steps(3000);
+
+            // Every living block and daughter block is allocated, and no two share a cell
+            let mut owner = vec![None; 4096];
+            let blocks = sim.organisms.iter().filter(|o| o.alive).flat_map(|o| {
+                std::iter::once((o.id, o.address, o.size)).chain(o.daughter.map(|d| (o.id, d.address, d.size)))
+            });
+            let mut cells = 0;
+            for (id, addr, size) in blocks {
+                for i in 0..size {
+                    let cell = &mut owner[(addr + i) % 4096];
+                    assert_eq!(*cell, None, "{}: organism {} overlaps organism {:?}", placement, id, cell);
+                    *cell = Some(id);
+                }
+                cells += size;
+            }
+            assert_eq!(sim.memory.count_used_cells(), cells, "{}: allocation map disagrees with living blocks", placement);
+            assert!(sim.stats.successful_replications > 10, "{} never let the ancestor replicate", placement);
+        }
+    }
+
+    #[test]
+    fn test_every_topology_replicates_within_its_neighbourhoods() {
+        let topologies = [
+            Topology::Linear,
+            Topology::Circular,
+            Topology::Torus { width: 64, radius: 4 },
+            Topology::Islands { count: 4, migration_rate: 0.0 },
+        ];
+        for topology in topologies {
+            let mut sim = Simulator::new(SimulationConfig {
+                memory_size: 4096,
+                topology,
+                max_population: 60,
+                seed: Some(9),
+                ..Default::default()
+            }).unwrap();
+            sim.initialize_with_ancestor();
+            let island = topology.island_of(sim.organisms[0].address, 4096);
+            for _ in 0..30 {
+                sim.run_steps(100);
+                for o in sim.organisms.iter().filter(|o| o.alive) {
+                    assert!(topology.allows_block(o.address, o.size, 4096), "{}: block at {} crosses an edge", topology, o.address);
+                    assert_eq!(topology.island_of(o.address, 4096), island, "{}: organism left the seed island", topology);
+                    if let (Topology::Torus { radius, .. }, Some(d)) = (topology, o.daughter) {
+                        assert!(topology.distance(o.address, d.address, 4096) <= radius, "{}: daughter placed too far", topology);
+                    }
                 }
             }
+            assert!(sim.stats.successful_replications > 10, "{} never let the ancestor replicate", topology);
         }
 
-        // Sanity check: used + free should equal total
-        assert_eq!(used_cells + free_cells, memory_size,
-            "Memory accounting error: used + free != total");
+        // With migration daughters reach the other islands
+        let mut sim = Simulator::new(SimulationConfig {
+            memory_size: 4096,
+            topology: Topology::Islands { count: 4, migration_rate: 0.2 },
+            max_population: 60,
+            seed: Some(9),
+            ..Default::default()
+        }).unwrap();
+        sim.initialize_with_ancestor();
+        sim.run_steps(3000);
+        let islands: std::collections::HashSet<usize> =
+            sim.organisms.iter().filter(|o| o.alive).map(|o| sim.memory.topology().island_of(o.address, 4096)).collect();
+        assert!(islands.len() > 1 && sim.stats.migrations > 0);
+    }
 
-        // The used cells might be more than total organism size due to:
-        // 1. Allocated but not yet fully used memory (offspring being created)
-        // 2. Fragmentation
-        // 3. Dead organisms that haven't been reaped yet
-        //
-        // What we can check is that there's enough allocated space for all
-        // alive organisms, and that used memory is reasonable
-        assert!(used_cells >= total_organism_size * 4 / 5,
-            "Used cells ({}) is suspiciously less than total organism size ({})",
-            used_cells, total_organism_size);
+    #[test]
+    fn test_invalid_topology_is_rejected_on_new_and_reset() {
+        let torus = SimulationConfig { memory_size: 4096, topology: Topology::Torus { width: 0, radius: 1 }, ..Default::default() };
+        assert!(Simulator::new(torus).err().unwrap().contains("Torus width"));
 
-        // Also check that we're not using more than 80% of memory
-        // (should have plenty of free space with small population)
-        assert!(used_cells < memory_size * 4 / 5,
-            "Using too much memory: {} / {} bytes", used_cells, memory_size);
+        let mut sim = Simulator::new(SimulationConfig { memory_size: 4096, seed: Some(1), ..Default::default() }).unwrap();
+        sim.initialize_with_ancestor();
+        sim.config.topology = Topology::Islands { count: 4, migration_rate: f64::NAN };
+        assert!(sim.reset().unwrap_err().contains("migration rate"));
+        assert_eq!(sim.organisms.len(), 1);
+        assert_eq!(sim.memory.topology(), Topology::Circular);
+
+        // Compaction would carry organisms out of a torus neighbourhood
+        sim.config.topology = Topology::Torus { width: 64, radius: 4 };
+        sim.config.compaction = true;
+        assert_eq!(sim.reset().unwrap_err(), "Compaction needs a linear or circular topology, not torus");
+        sim.config.compaction = false;
+        assert!(sim.reset().is_ok());
+    }
 
-        println!("✓ Memory tracking integrity check passed");
+    #[test]
+    fn test_compaction_mid_copy_keeps_replication_exact() {
+        let mut sim = Simulator::new(SimulationConfig {
+            memory_size: 4096,
+            mutation_rate: 0.0,
+            max_population: 60,
+            seed: Some(4),
+            ..Default::default()
+        }).unwrap();
+        sim.initialize_with_ancestor();
+        let ancestor = ancestor(sim.cpu.isa);
+
+        let mut moved = 0;
+        let mut mid_copy = 0;
+        for _ in 0..30 {
+            sim.run_steps(97);
+            mid_copy += sim.organisms.iter().filter(|o| o.alive && o.daughter.is_some()).count();
+            let genomes: Vec<_> = sim.organisms.iter().filter(|o| o.alive).map(|o| sim.memory.get_slice(o.address, o.size)).collect();
+            moved += sim.compact();
+
+            let alive: Vec<_> = sim.organisms.iter().filter(|o| o.alive).collect();
+            let used: usize = alive.iter().map(|o| o.size + o.daughter.map_or(0, |d| d.size)).sum();
+            assert_eq!(sim.memory.allocated_runs(), vec![(0, used)]);
+            for (organism, genome) in alive.iter().zip(genomes) {
+                assert_eq!(sim.memory.get_slice(organism.address, organism.size), genome);
+                assert!(organism.is_own_address(organism.ip, 4096), "IP left organism {}", organism.id);
+            }
+        }
+        assert!(moved > 0 && mid_copy > 0, "compaction never moved a block being copied");
+        assert_eq!(sim.stats.compactions, 30);
+
+        // Without mutations, a pointer left behind would show up as a bad copy
+        let born = sim.stats.successful_replications;
+        assert!(born > 50, "only {} births", born);
+        for organism in sim.organisms.iter().filter(|o| o.alive) {
+            assert_eq!(sim.memory.get_slice(organism.address, organism.size), ancestor, "organism {} is not an exact copy", organism.id);
+        }
+    }
+
+    #[test]
+    fn test_compaction_moves_pointers_just_past_the_block() {
+        let mut sim = Simulator::new(SimulationConfig { memory_size: 1000, mutation_rate: 0.0, ..Default::default() }).unwrap();
+        let genome = ancestor(sim.cpu.isa);
+        let id = sim.inoculate(&genome, Some(500), 1)[0];
+
+        // AdrF leaves AX just past the end template, which is the end of the block
+        while sim.memory.read(sim.organisms[0].ip) != Instruction::SubAB {
+            sim.step_organism(id);
+        }
+        assert_eq!(sim.organisms[0].ax, 546);
+        assert_eq!(sim.compact(), 1);
+        assert_eq!((sim.organisms[0].address, sim.organisms[0].ip, sim.organisms[0].ax, sim.organisms[0].bx), (0, 20, 46, 0));
+
+        while sim.organisms.len() < 2 {
+            sim.step_organism(id);
+        }
+        assert_eq!(sim.memory.get_slice(sim.organisms[1].address, sim.organisms[1].size), genome);
+    }
+
+    #[test]
+    fn test_compaction_makes_room_before_the_reaper() {
+        for compaction in [false, true] {
+            let mut sim = Simulator::new(SimulationConfig {
+                memory_size: 1000,
+                reaper_threshold: 1.0,
+                compaction,
+                seed: Some(1),
+                ..Default::default()
+            }).unwrap();
+            let genome = ancestor(sim.cpu.isa);
+            // Ten ancestors 100 cells apart leave only 54-cell holes
+            for addr in (0..1000).step_by(100) {
+                sim.inoculate(&genome, Some(addr), 1);
+            }
+            assert_eq!(sim.memory.largest_free_block(), 54);
+
+            let addr = sim.allocate_with_reaper(80, 0);
+            assert!(addr.is_some());
+            if compaction {
+                assert_eq!((sim.stats.reaper_kills, sim.stats.compactions), (0, 1));
+                assert_eq!(sim.organisms.iter().map(|o| o.address).collect::<Vec<_>>(), (0..460).step_by(46).collect::<Vec<_>>());
+            } else {
+                assert!(sim.stats.reaper_kills > 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_seed_produces_identical_soups() {
+        let config = SimulationConfig {
+            memory_size: 8192,
+            mutation_rate: 0.01, // Mutations exercise the RNG on every copy
+            max_population: 20,
+            seed: Some(42),
+            ..Default::default()
+        };
+
+        let mut sim_a = Simulator::new(config.clone()).unwrap();
+        let mut sim_b = Simulator::new(config).unwrap();
+        sim_a.initialize_with_ancestor();
+        sim_b.initialize_with_ancestor();
+        let first_addr = sim_a.organisms[0].address;
+
+        for _ in 0..20000 {
+            sim_a.step();
+            sim_b.step();
+        }
+
+        assert!(sim_a.stats.successful_replications > 0, "No replication happened");
+        assert_eq!(sim_a.stats.total_mutations, sim_b.stats.total_mutations);
+        assert_eq!(
+            sim_a.memory.get_slice(0, sim_a.memory.size()),
+            sim_b.memory.get_slice(0, sim_b.memory.size()),
+            "Soups diverged despite identical seeds"
+        );
+
+        let layout = |sim: &Simulator| -> Vec<(usize, usize, usize)> {
+            sim.organisms.iter().map(|o| (o.id, o.address, o.size)).collect()
+        };
+        assert_eq!(layout(&sim_a), layout(&sim_b));
+
+        // Reset must replay the same run from the configured seed
+        sim_a.reset().unwrap();
+        sim_a.initialize_with_ancestor();
+        assert_eq!(sim_a.organisms[0].address, first_addr);
+    }
+
+    #[test]
+    fn test_genebank_tracks_living_genotypes() {
+        let config = SimulationConfig {
+            memory_size: 16384,
+            mutation_rate: 0.01,
+            max_population: 30,
+            seed: Some(5),
+            ..Default::default()
+        };
+
+        let mut sim = Simulator::new(config).unwrap();
+        sim.initialize_with_ancestor();
+        let ancestor_size = sim.organisms[0].size;
+        assert_eq!(sim.genebank.get(0).unwrap().name, format!("{:04}aaa", ancestor_size));
+
+        sim.run_steps(20000);
+
+        // Every living organism is accounted for by exactly one genotype
+        let alive = sim.organisms.iter().filter(|o| o.alive).count();
+        let abundance: usize = sim.genebank.living().map(|g| g.abundance).sum();
+        assert_eq!(abundance, alive);
+
+        // Genotypes are fixed at birth, so sizes must agree
+        for org in sim.organisms.iter().filter(|o| o.alive) {
+            let genotype = sim.genebank.get(org.genotype.unwrap()).unwrap();
+            assert_eq!(genotype.size(), org.size);
+        }
+    }
+
+    #[test]
+    fn test_bundled_ancestor_file_matches_builtin() {
+        let genome = crate::assembler::assemble(include_str!("../genomes/ancestor.tie"), Isa::TierraRs).unwrap();
+        assert_eq!(genome, create_ancestor());
+        let tierra0 = crate::assembler::assemble(include_str!("../genomes/ancestor-tierra0.tie"), Isa::Tierra0).unwrap();
+        assert_eq!(tierra0, create_tierra0_ancestor());
+
+        let mut sim = Simulator::new(SimulationConfig { seed: Some(2), ..Default::default() }).unwrap();
+        let id = sim.initialize_with_genome(&genome).unwrap();
+        assert_eq!(sim.organisms[0].id, id);
+        assert_eq!(sim.memory.get_slice(sim.organisms[0].address, genome.len()), genome);
+    }
+
+    #[test]
+    fn test_tierra0_ancestor_replicates_exactly() {
+        let mut sim = Simulator::new(SimulationConfig {
+            memory_size: 8192,
+            isa: Isa::Tierra0,
+            mutation_rate: 0.0,
+            seed: Some(5),
+            ..Default::default()
+        }).unwrap();
+        sim.initialize_with_ancestor();
+        assert_eq!(sim.organisms[0].size, 46);
+        while sim.organisms.len() < 3 {
+            sim.step();
+            assert!(sim.stats.total_instructions < 100_000, "tierra0 ancestor never divided twice");
+        }
+        let genome = sim.memory.get_slice(sim.organisms[0].address, 46);
+        for org in &sim.organisms {
+            assert_eq!(sim.memory.get_slice(org.address, org.size), genome);
+        }
+    }
+
+    #[test]
+    fn test_profile_counts_one_replication() {
+        let mut sim = Simulator::new(SimulationConfig { memory_size: 4096, seed: Some(2), ..Default::default() }).unwrap();
+        sim.initialize_with_ancestor();
+        let id = sim.organisms[0].id;
+        while sim.organisms.len() < 2 {
+            sim.step_organism(id);
+        }
+
+        let profile = &sim.cpu.profile;
+        let count = |inst| profile.instruction(inst);
+        assert_eq!(count(Instruction::MovII).executions, 46);
+        assert_eq!(count(Instruction::JmpB).executions, 45);
+        assert_eq!(count(Instruction::JmpB).searches, 45);
+        assert_eq!(count(Instruction::JmpF).executions, 1);
+        assert_eq!(profile.total().failed_searches, 0);
+        assert_eq!(profile.total().errors, 0);
+        // AdrB's template starts 9 cells after the start template, AdrF's 26 cells before the end template
+        assert_eq!(count(Instruction::AdrB).average_search_distance(), Some(9.0));
+        assert_eq!(count(Instruction::AdrF).average_search_distance(), Some(26.0));
+
+        let genotype = sim.organisms[0].genotype.unwrap();
+        assert_eq!(profile.genotype(genotype), profile.total());
+
+        sim.update_stats();
+        assert_eq!(sim.stats.profile, sim.cpu.profile.summary());
+
+        // The genotype's counters go when its last member dies
+        sim.kill_organism(0);
+        assert_eq!(sim.cpu.profile.genotype(genotype), sim.cpu.profile.total());
+        sim.kill_organism(1);
+        assert_eq!(sim.cpu.profile.genotype(genotype), crate::profile::OpcodeCounters::default());
+        assert_eq!(sim.cpu.profile.instruction(Instruction::MovII).executions, 46);
+    }
+
+    #[test]
+    fn test_inoculate_places_copies_at_hint_into_running_soup() {
+        let config = SimulationConfig {
+            memory_size: 8192,
+            max_population: 50,
+            seed: Some(4),
+            ..Default::default()
+        };
+        let mut sim = Simulator::new(config).unwrap();
+        sim.initialize_with_ancestor();
+        sim.run_steps(500);
+
+        let parasite = vec![Instruction::Nop1, Instruction::Nop0, Instruction::IncA, Instruction::Halt];
+        let before = sim.organisms.len();
+        let ids = sim.inoculate(&parasite, Some(8000), 3);
+        assert_eq!(ids.len(), 3);
+        assert_eq!(sim.organisms.len(), before + 3);
+
+        let placed: Vec<_> = sim.organisms.iter().filter(|o| ids.contains(&o.id)).collect();
+        assert_eq!(placed[0].address, 8000);
+        assert_eq!(placed[1].address, 8004);
+        assert_eq!(placed[2].address, 8008);
+        for org in placed {
+            assert_eq!(sim.memory.get_slice(org.address, org.size), parasite);
+        }
+
+        let genotype = sim.genebank.find_by_genome(&parasite).unwrap();
+        assert_eq!(genotype.abundance, 3);
+        assert_eq!(genotype.parent, None);
+
+        // A soup that is too small stops early instead of overlapping organisms
+        let mut tiny = Simulator::new(SimulationConfig { memory_size: 10, seed: Some(1), ..Default::default() }).unwrap();
+        assert_eq!(tiny.inoculate(&parasite, None, 5).len(), 2);
+    }
+
+    #[test]
+    fn test_mutation_classes_are_counted_separately() {
+        let config = SimulationConfig {
+            memory_size: 16384,
+            mutation_rate: 0.0,
+            cosmic_ray_rate: 0.01,
+            flaw_rate: 0.001,
+            insertion_rate: 0.5,
+            deletion_rate: 0.5,
+            crossover_rate: 0.5,
+            max_population: 30,
+            seed: Some(8),
+            ..Default::default()
+        };
+
+        let mut sim = Simulator::new(config).unwrap();
+        sim.initialize_with_ancestor();
+        sim.run_steps(5000);
+
+        let stats = &sim.stats;
+        assert_eq!(stats.copy_mutations, 0, "Copy errors are disabled");
+        assert!(stats.cosmic_ray_mutations > 0);
+        assert!(stats.flaws > 0);
+        assert!(stats.insertions > 0);
+        assert!(stats.deletions > 0);
+        assert!(stats.crossovers > 0);
+        assert_eq!(
+            stats.total_mutations,
+            stats.cosmic_ray_mutations + stats.flaws + stats.insertions + stats.deletions + stats.crossovers
+        );
+
+        // Insertions and deletions must keep the allocation map consistent
+        let alive: Vec<_> = sim.organisms.iter().filter(|o| o.alive).collect();
+        assert!(alive.iter().any(|o| o.size != 46), "Indels never changed a genome length");
+        for org in &alive {
+            assert!(sim.memory.allocated_runs().iter().any(|&(start, len)| {
+                org.address >= start && org.address + org.size <= start + len
+            }), "Organism at {} (size {}) is not fully allocated", org.address, org.size);
+        }
+    }
+
+    #[test]
+    fn test_insertion_at_the_soup_end_follows_the_topology() {
+        for (topology, grown) in [(Topology::Circular, 11), (Topology::Linear, 10)] {
+            let config = SimulationConfig { memory_size: 1000, insertion_rate: 1.0, topology, seed: Some(1), ..Default::default() };
+            let mut sim = Simulator::new(config).unwrap();
+            assert!(sim.memory.allocate_at(990, 10));
+
+            // A circular soup grows the block round to cell 0; a linear one ends at 1000
+            assert_eq!(sim.mutate_daughter(0, 990, 10), grown);
+            let expected = if grown == 11 { vec![(0, 1), (990, 10)] } else { vec![(990, 10)] };
+            assert_eq!(sim.memory.allocated_runs(), expected);
+        }
+    }
+
+    #[test]
+    fn test_reaper_keeps_memory_below_threshold() {
+        let config = SimulationConfig {
+            memory_size: 4096,
+            mutation_rate: 0.0,
+            reaper_threshold: 0.5,
+            max_population: 1000,
+            seed: Some(9),
+            ..Default::default()
+        };
+
+        let mut sim = Simulator::new(config).unwrap();
+        sim.initialize_with_ancestor();
+        let ancestor_size = sim.organisms[0].size;
+        sim.run_steps(30000);
+
+        // The soup only holds ~26 ancestors below the threshold, so the
+        // population must have turned over rather than stalling
+        assert!(sim.stats.reaper_kills > 0);
+        assert!(sim.stats.successful_replications > 26);
+        assert!(sim.memory.count_used_cells() <= 2048 + ancestor_size);
+
+        // Every living organism is queued exactly once, and the dead are gone
+        let alive: Vec<usize> = sim.organisms.iter().filter(|o| o.alive).map(|o| o.id).collect();
+        assert_eq!(sim.reaper.len(), alive.len());
+        assert!(alive.iter().all(|&id| sim.reaper.position(id).is_some()));
+    }
+
+    #[test]
+    fn test_lineage_keeps_only_ancestry_of_the_living() {
+        let config = SimulationConfig {
+            memory_size: 8192,
+            mutation_rate: 0.0,
+            max_population: 30,
+            seed: Some(11),
+            ..Default::default()
+        };
+
+        let mut sim = Simulator::new(config).unwrap();
+        sim.initialize_with_ancestor();
+        sim.run_steps(30000);
+        assert!(sim.stats.total_organisms_died > 30, "Population never turned over");
+
+        let alive = sim.organisms.iter().filter(|o| o.alive).count();
+        assert_eq!(sim.lineage.iter().filter(|n| n.is_alive()).count(), alive);
+        for node in sim.lineage.iter().filter(|n| !n.is_alive()) {
+            // Dead ancestors survive only as roots or branch points
+            assert!(node.parent.is_none() || node.children.len() >= 2);
+        }
+        assert!(sim.lineage.len() < 2 * alive + 1);
+
+        let newick = sim.lineage.to_newick(&sim.genebank);
+        assert!(newick.ends_with(';'));
+        assert_eq!(newick.matches('(').count(), newick.matches(')').count());
+    }
+
+    #[test]
+    fn test_zero_size_malloc_does_not_trigger_reaper() {
+        let mut sim = Simulator::new(SimulationConfig { memory_size: 4096, seed: Some(4), ..Default::default() }).unwrap();
+        sim.initialize_with_ancestor();
+        sim.initialize_with_ancestor();
+        // AX starts at zero, so every MallocA asks for nothing
+        sim.inoculate(&[Instruction::MallocA], None, 1);
+
+        sim.run_steps(3);
+        assert_eq!(sim.stats.reaper_kills, 0);
+        assert_eq!(sim.organisms.iter().filter(|o| o.alive).count(), 3);
+        assert!(sim.organisms[2].errors > 0);
+    }
+
+    #[test]
+    fn test_one_pending_daughter_validated_on_divide() {
+        use Instruction::*;
+
+        let mut sim = Simulator::new(SimulationConfig { memory_size: 4096, seed: Some(4), ..Default::default() }).unwrap();
+        // CX = 8, AX = 8, then two allocations and a Divide with nothing copied
+        let genome = [Zero, Not0, Shl, Shl, Shl, PushC, PopA, MallocA, MallocA, MovII, Divide];
+        let id = sim.inoculate(&genome, None, 1)[0];
+        for _ in 0..8 {
+            sim.step_organism(id);
+        }
+        let first = sim.organisms[0].daughter.unwrap();
+        assert_eq!((first.size, first.written), (8, 0));
+
+        // A second MallocA replaces the first block instead of leaking it
+        sim.step_organism(id);
+        let daughter = sim.organisms[0].daughter.unwrap();
+        assert_eq!(sim.organisms[0].bx, daughter.address);
+        assert_eq!(sim.memory.count_used_cells(), genome.len() + 8);
+
+        // One cell copied is too little to divide; the block stays pending
+        sim.step_organism(id);
+        assert_eq!(sim.organisms[0].daughter.unwrap().written, 1);
+        sim.step_organism(id);
+        assert_eq!(sim.organisms.len(), 1);
+        assert_eq!(sim.organisms[0].daughter, Some(Daughter { written: 1, ..daughter }));
+        assert_eq!((sim.organisms[0].errors, sim.stats.failed_replications), (1, 1));
+
+        sim.config.divide_min_written = 0.1;
+        sim.organisms[0].ip = sim.organisms[0].address + genome.len() - 1;
+        sim.step_organism(id);
+        assert_eq!(sim.organisms.len(), 2);
+        assert_eq!((sim.organisms[1].address, sim.organisms[1].size), (daughter.address, 8));
+        assert_eq!(sim.organisms[0].daughter, None);
+
+        // Dying frees a pending daughter along with the organism
+        sim.organisms[0].ip = sim.organisms[0].address + 7;
+        sim.step_organism(id);
+        sim.kill_organism(0);
+        assert_eq!(sim.memory.count_used_cells(), 8);
+    }
+
+    #[test]
+    fn test_template_match_in_neighbour_is_recorded_as_interaction() {
+        use crate::interaction::Role;
+
+        let mut sim = Simulator::new(SimulationConfig { memory_size: 4096, seed: Some(3), ..Default::default() }).unwrap();
+        // A's jump template only has a complement inside B, which sits right after it
+        let a = [Instruction::JmpF, Instruction::Nop0, Instruction::Nop0, Instruction::IncA];
+        let b = [Instruction::Nop1, Instruction::Nop1, Instruction::IncB, Instruction::IncB];
+        let a_id = sim.inoculate(&a, Some(100), 1)[0];
+        sim.inoculate(&b, Some(100), 1);
+        assert_eq!(sim.organisms[1].address, 104);
+
+        sim.step_organism(a_id);
+        let (ga, gb) = (sim.organisms[0].genotype.unwrap(), sim.organisms[1].genotype.unwrap());
+        assert_eq!(sim.interactions.count(ga, gb), 1);
+        assert_eq!(sim.interactions.role(ga), Role::Parasite);
+        assert_eq!(sim.interactions.role(gb), Role::SelfSufficient);
+
+        // B's own instructions never touch foreign code
+        sim.step_organism(sim.organisms[1].id);
+        assert_eq!(sim.interactions.len(), 1);
+    }
+
+    #[test]
+    fn test_foreign_code_is_executable_but_not_writable() {
+        let mut sim = Simulator::new(SimulationConfig { memory_size: 4096, seed: Some(3), ..Default::default() }).unwrap();
+        let a = [Instruction::JmpF, Instruction::Nop0, Instruction::Nop0, Instruction::IncA];
+        let b = [Instruction::Nop1, Instruction::Nop1, Instruction::IncB, Instruction::MovII];
+        let a_id = sim.inoculate(&a, Some(100), 1)[0];
+        sim.inoculate(&b, Some(100), 1);
+
+        // The jump lands just past B's template and A runs B's code
+        sim.step_organism(a_id);
+        assert_eq!(sim.organisms[0].ip, 106);
+        sim.step_organism(a_id);
+        assert_eq!(sim.organisms[0].bx, 1);
+        assert_eq!(sim.organisms[0].ip, 107);
+
+        // MovII from inside B may not write into B
+        sim.organisms[0].bx = 105;
+        sim.step_organism(a_id);
+        assert_eq!(sim.memory.read(105), Instruction::Nop1);
+        assert_eq!(sim.organisms[0].errors, 1);
+
+        // Without foreign execute permission the jump fails and A stays home
+        sim.config.permissions = "foreign=r".parse().unwrap();
+        let c_id = sim.inoculate(&a, Some(200), 1)[0];
+        sim.step_organism(c_id);
+        assert_eq!(sim.organisms[2].errors, 1);
+        assert_eq!(sim.organisms[2].ip, 202);
+    }
+
+    #[test]
+    fn test_template_searches_need_read_permission() {
+        let mut sim = Simulator::new(SimulationConfig { memory_size: 4096, seed: Some(3), ..Default::default() }).unwrap();
+        let a = [Instruction::AdrF, Instruction::Nop0, Instruction::Nop0, Instruction::IncA];
+        let b = [Instruction::Nop1, Instruction::Nop1, Instruction::IncB];
+        let a_id = sim.inoculate(&a, Some(100), 1)[0];
+        sim.inoculate(&b, Some(100), 1);
+        sim.step_organism(a_id);
+        assert_eq!(sim.organisms[0].ax, 106);
+
+        // Without foreign read permission B's template cannot be matched
+        sim.config.permissions = "foreign=x".parse().unwrap();
+        let c_id = sim.inoculate(&a, Some(200), 1)[0];
+        sim.inoculate(&b, Some(200), 1);
+        sim.step_organism(c_id);
+        assert_eq!((sim.organisms[2].ax, sim.organisms[2].errors), (0, 1));
+    }
+
+    #[test]
+    fn test_mov_dc_and_mov_cd_address_the_soup() {
+        let mut sim = Simulator::new(SimulationConfig { memory_size: 4096, seed: Some(3), ..Default::default() }).unwrap();
+        let a = [Instruction::MovDC, Instruction::MovCD];
+        let b = [Instruction::IncB, Instruction::Nop1];
+        let a_id = sim.inoculate(&a, Some(100), 1)[0];
+        sim.inoculate(&b, Some(200), 1);
+
+        // CX is an absolute address, so A reads B's first cell
+        sim.organisms[0].cx = 200;
+        sim.step_organism(a_id);
+        assert_eq!(sim.organisms[0].dx, Instruction::IncB.to_u8() as usize);
+        assert_eq!(sim.organisms[0].errors, 0);
+
+        // Writing into B is refused
+        sim.step_organism(a_id);
+        assert_eq!(sim.memory.read(200), Instruction::IncB);
+        assert_eq!(sim.organisms[0].errors, 1);
     }
 }
@@ -0,0 +1,108 @@
+use crate::simulator::Simulator;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Current snapshot format version. Bump whenever the serialized layout of
+/// `Simulator` (or anything it contains) changes incompatibly.
+pub const SNAPSHOT_VERSION: u32 = 21;
+
+#[derive(Serialize)]
+struct SnapshotOut<'a> {
+    version: u32,
+    simulator: &'a Simulator,
+}
+
+#[derive(Deserialize)]
+struct SnapshotIn {
+    version: u32,
+    simulator: serde_json::Value,
+}
+
+/// Serialize the full simulator state (soup, allocation map, organisms,
+/// scheduler position, statistics and RNG) to a JSON string
+pub fn to_string(sim: &Simulator) -> Result<String, String> {
+    serde_json::to_string(&SnapshotOut {
+        version: SNAPSHOT_VERSION,
+        simulator: sim,
+    })
+    .map_err(|e| format!("Failed to serialize snapshot: {}", e))
+}
+
+/// Restore a simulator from a JSON snapshot string
+pub fn from_str(text: &str) -> Result<Simulator, String> {
+    let snapshot: SnapshotIn =
+        serde_json::from_str(text).map_err(|e| format!("Invalid snapshot: {}", e))?;
+
+    if snapshot.version != SNAPSHOT_VERSION {
+        return Err(format!(
+            "Unsupported snapshot version {} (expected {})",
+            snapshot.version, SNAPSHOT_VERSION
+        ));
+    }
+
+    serde_json::from_value(snapshot.simulator).map_err(|e| format!("Invalid snapshot: {}", e))
+}
+
+/// Write a snapshot of the simulator to a file
+pub fn save(sim: &Simulator, path: impl AsRef<Path>) -> Result<(), String> {
+    let path = path.as_ref();
+    let text = to_string(sim)?;
+    fs::write(path, text).map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+}
+
+/// Load a simulator from a snapshot file
+pub fn load(path: impl AsRef<Path>) -> Result<Simulator, String> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    from_str(&text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::SimulationConfig;
+
+    #[test]
+    fn test_snapshot_resume_matches_uninterrupted_run() {
+        let config = SimulationConfig {
+            memory_size: 8192,
+            mutation_rate: 0.01,
+            max_population: 20,
+            seed: Some(9),
+            ..Default::default()
+        };
+
+        let mut original = Simulator::new(config).unwrap();
+        original.initialize_with_ancestor();
+        original.run_steps(5000);
+
+        let mut resumed = from_str(&to_string(&original).unwrap()).unwrap();
+        assert_eq!(resumed.memory.allocated_runs(), original.memory.allocated_runs());
+
+        original.run_steps(5000);
+        resumed.run_steps(5000);
+
+        assert_eq!(
+            resumed.memory.get_slice(0, resumed.memory.size()),
+            original.memory.get_slice(0, original.memory.size())
+        );
+        assert_eq!(resumed.stats.total_instructions, original.stats.total_instructions);
+        assert_eq!(resumed.stats.total_mutations, original.stats.total_mutations);
+        assert_eq!(resumed.organisms.len(), original.organisms.len());
+    }
+
+    #[test]
+    fn test_snapshot_rejects_other_versions() {
+        let sim = Simulator::new(SimulationConfig { memory_size: 64, ..Default::default() }).unwrap();
+        let text = to_string(&sim).unwrap().replacen(
+            &format!("\"version\":{}", SNAPSHOT_VERSION),
+            "\"version\":0",
+            1,
+        );
+        match from_str(&text) {
+            Err(message) => assert!(message.contains("version"), "{}", message),
+            Ok(_) => panic!("Snapshot with a foreign version was accepted"),
+        }
+    }
+}
@@ -1,7 +1,11 @@
-use std::collections::HashMap;
+use crate::diversity::{Diversity, DiversityTracker};
+use crate::mutation::MutationKind;
+use crate::profile::ProfileSummary;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 
 /// Statistics tracker for the simulation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Statistics {
     /// Total number of instructions executed
     pub total_instructions: u64,
@@ -18,6 +22,17 @@ pub struct Statistics {
     /// Mutations applied
     pub total_mutations: u64,
 
+    /// Mutations by kind
+    pub cosmic_ray_mutations: u64,
+    pub copy_mutations: u64,
+    pub flaws: u64,
+    pub insertions: u64,
+    pub deletions: u64,
+    pub crossovers: u64,
+
+    /// Deaths caused by the reaper
+    pub reaper_kills: u64,
+
     /// Failed replications
     pub failed_replications: u64,
 
@@ -34,8 +49,25 @@ pub struct Statistics {
     pub memory_used: usize,
     pub memory_total: usize,
 
+    /// Longest free region and number of free regions
+    pub largest_free_block: usize,
+    pub free_blocks: usize,
+
+    /// Compaction passes run
+    pub compactions: u64,
+
+    /// Daughters placed on another island than their mother's
+    pub migrations: u64,
+
+    /// Latest genotype diversity measurement
+    pub diversity: Diversity,
+    pub diversity_tracker: DiversityTracker,
+
+    /// The CPU's per-instruction profile as of the latest update
+    pub profile: ProfileSummary,
+
     /// History for graphing
-    pub population_history: Vec<usize>,
+    pub population_history: VecDeque<usize>,
     pub max_history_size: usize,
 }
 
@@ -47,13 +79,27 @@ impl Statistics {
             total_organisms_died: 0,
             current_population: 0,
             total_mutations: 0,
+            cosmic_ray_mutations: 0,
+            copy_mutations: 0,
+            flaws: 0,
+            insertions: 0,
+            deletions: 0,
+            crossovers: 0,
+            reaper_kills: 0,
             failed_replications: 0,
             successful_replications: 0,
             size_distribution: HashMap::new(),
             generation_distribution: HashMap::new(),
             memory_used: 0,
             memory_total,
-            population_history: Vec::new(),
+            largest_free_block: memory_total,
+            free_blocks: 1,
+            compactions: 0,
+            migrations: 0,
+            diversity: Diversity::default(),
+            diversity_tracker: DiversityTracker::new(),
+            profile: ProfileSummary::default(),
+            population_history: VecDeque::new(),
             max_history_size: 1000,
         }
     }
@@ -91,9 +137,22 @@ impl Statistics {
         }
     }
 
+    /// Record a death caused by the reaper (the death itself is recorded separately)
+    pub fn record_reap(&mut self) {
+        self.reaper_kills += 1;
+    }
+
     /// Record a mutation
-    pub fn record_mutation(&mut self) {
+    pub fn record_mutation(&mut self, kind: MutationKind) {
         self.total_mutations += 1;
+        match kind {
+            MutationKind::CosmicRay => self.cosmic_ray_mutations += 1,
+            MutationKind::CopyError => self.copy_mutations += 1,
+            MutationKind::Flaw => self.flaws += 1,
+            MutationKind::Insertion => self.insertions += 1,
+            MutationKind::Deletion => self.deletions += 1,
+            MutationKind::Crossover => self.crossovers += 1,
+        }
     }
 
     /// Record a replication attempt
@@ -110,11 +169,38 @@ impl Statistics {
         self.memory_used = used;
     }
 
+    /// Update the fragmentation measurements
+    pub fn update_fragmentation(&mut self, largest_free_block: usize, free_blocks: usize) {
+        self.largest_free_block = largest_free_block;
+        self.free_blocks = free_blocks;
+    }
+
+    /// Record a compaction pass
+    pub fn record_compaction(&mut self) {
+        self.compactions += 1;
+    }
+
+    /// Record a daughter placed on another island
+    pub fn record_migration(&mut self) {
+        self.migrations += 1;
+    }
+
+    /// Share of free memory outside the largest free block: 0 when free
+    /// memory is one block, approaching 1 as it splinters
+    pub fn fragmentation(&self) -> f64 {
+        let free = self.memory_total.saturating_sub(self.memory_used);
+        if free > 0 {
+            1.0 - self.largest_free_block as f64 / free as f64
+        } else {
+            0.0
+        }
+    }
+
     /// Update population history for graphing
     pub fn update_history(&mut self, population: usize) {
-        self.population_history.push(population);
+        self.population_history.push_back(population);
         if self.population_history.len() > self.max_history_size {
-            self.population_history.remove(0);
+            self.population_history.pop_front();
         }
     }
 
@@ -149,6 +235,23 @@ impl Statistics {
     pub fn highest_generation(&self) -> usize {
         self.generation_distribution.keys().max().copied().unwrap_or(0)
     }
+
+    /// One-line summary for log output
+    pub fn summary(&self) -> String {
+        format!(
+            "inst={} pop={} born={} died={} mut={} repl={}/{} mem={:.1}% common_size={} max_gen={}",
+            self.total_instructions,
+            self.current_population,
+            self.total_organisms_created,
+            self.total_organisms_died,
+            self.total_mutations,
+            self.successful_replications,
+            self.successful_replications + self.failed_replications,
+            self.memory_usage_percent(),
+            self.most_common_size().map_or_else(|| "-".to_string(), |size| size.to_string()),
+            self.highest_generation(),
+        )
+    }
 }
 
 impl Default for Statistics {
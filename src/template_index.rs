@@ -0,0 +1,160 @@
+use crate::instruction::Instruction;
+
+/// Bitmaps of the soup's Nop0 and Nop1 cells, kept up to date on every
+/// write, so complement templates can be found 64 cells at a time instead
+/// of reading every cell within the search distance.
+///
+/// Shifting each nop's bitmap by the template offset it must appear at and
+/// intersecting the results leaves one bit per position a match starts at.
+/// Bits past the end of the soup are always clear, so this only finds
+/// matches that do not wrap; the few positions from which a match would wrap
+/// are checked cell by cell.
+///
+/// A search still walks every word within its distance, so it is a
+/// constant-factor improvement on a scan, not a sub-linear one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateIndex {
+    /// Bit `addr % 64` of word `addr / 64` is set where the cell holds Nop0
+    nop0: Vec<u64>,
+    /// The same for Nop1
+    nop1: Vec<u64>,
+    size: usize,
+}
+
+impl TemplateIndex {
+    /// Index the given soup contents
+    pub fn new(data: &[Instruction]) -> Self {
+        let words = data.len().div_ceil(64);
+        let mut index = Self { nop0: vec![0; words], nop1: vec![0; words], size: data.len() };
+        for (addr, &inst) in data.iter().enumerate() {
+            index.set(addr, inst, true);
+        }
+        index
+    }
+
+    /// Account for the cell at `addr` changing from `old` to `new`
+    pub fn update(&mut self, addr: usize, old: Instruction, new: Instruction) {
+        self.set(addr, old, false);
+        self.set(addr, new, true);
+    }
+
+    fn set(&mut self, addr: usize, inst: Instruction, present: bool) {
+        let bits = match inst {
+            Instruction::Nop0 => &mut self.nop0,
+            Instruction::Nop1 => &mut self.nop1,
+            _ => return,
+        };
+        let bit = 1u64 << (addr % 64);
+        if present {
+            bits[addr / 64] |= bit;
+        } else {
+            bits[addr / 64] &= !bit;
+        }
+    }
+
+    /// First cell of the nearest occurrence of the nop sequence `pattern`
+    /// starting within `max_search` cells after `start`, like a cell-by-cell
+    /// forward scan
+    pub fn find_forward(&self, data: &[Instruction], start: usize, pattern: &[Instruction], max_search: usize) -> Option<usize> {
+        let window = max_search.min(self.size);
+        if pattern.is_empty() || window == 0 {
+            return None;
+        }
+
+        let lo = (start + 1) % self.size;
+        if lo + window <= self.size {
+            self.first_match(data, lo, lo + window - 1, pattern)
+        } else {
+            self.first_match(data, lo, self.size - 1, pattern)
+                .or_else(|| self.first_match(data, 0, lo + window - 1 - self.size, pattern))
+        }
+    }
+
+    /// First cell of the nearest occurrence of the nop sequence `pattern`
+    /// starting within `max_search` cells before `start`, like a
+    /// cell-by-cell backward scan
+    pub fn find_backward(&self, data: &[Instruction], start: usize, pattern: &[Instruction], max_search: usize) -> Option<usize> {
+        let window = max_search.min(self.size);
+        if pattern.is_empty() || window == 0 {
+            return None;
+        }
+
+        let hi = (start + self.size - 1) % self.size;
+        if hi + 1 >= window {
+            self.last_match(data, hi + 1 - window, hi, pattern)
+        } else {
+            self.last_match(data, 0, hi, pattern)
+                .or_else(|| self.last_match(data, self.size - (window - hi - 1), self.size - 1, pattern))
+        }
+    }
+
+    /// Lowest match start in `lo..=hi`
+    pub fn first_match(&self, data: &[Instruction], lo: usize, hi: usize, pattern: &[Instruction]) -> Option<usize> {
+        for word in lo / 64..=hi / 64 {
+            let starts = self.match_starts(word, pattern) & range_mask(word, lo, hi);
+            if starts != 0 {
+                return Some(word * 64 + starts.trailing_zeros() as usize);
+            }
+        }
+        (lo.max(self.wrap_start(pattern))..=hi).find(|&p| self.matches(data, p, pattern))
+    }
+
+    /// Highest match start in `lo..=hi`
+    pub fn last_match(&self, data: &[Instruction], lo: usize, hi: usize, pattern: &[Instruction]) -> Option<usize> {
+        if let Some(p) = (lo.max(self.wrap_start(pattern))..=hi).rev().find(|&p| self.matches(data, p, pattern)) {
+            return Some(p);
+        }
+        for word in (lo / 64..=hi / 64).rev() {
+            let starts = self.match_starts(word, pattern) & range_mask(word, lo, hi);
+            if starts != 0 {
+                return Some(word * 64 + 63 - starts.leading_zeros() as usize);
+            }
+        }
+        None
+    }
+
+    /// Bit `i` is set if a match of `pattern` that does not wrap starts at
+    /// cell `word * 64 + i`
+    fn match_starts(&self, word: usize, pattern: &[Instruction]) -> u64 {
+        let mut starts = !0;
+        for (offset, &inst) in pattern.iter().enumerate() {
+            let bits = match inst {
+                Instruction::Nop0 => &self.nop0,
+                Instruction::Nop1 => &self.nop1,
+                _ => return 0,
+            };
+            starts &= shifted_word(bits, word, offset);
+            if starts == 0 {
+                break;
+            }
+        }
+        starts
+    }
+
+    /// First position from which a match would wrap past the end of the soup
+    fn wrap_start(&self, pattern: &[Instruction]) -> usize {
+        (self.size + 1).saturating_sub(pattern.len())
+    }
+
+    fn matches(&self, data: &[Instruction], p: usize, pattern: &[Instruction]) -> bool {
+        pattern.iter().enumerate().all(|(i, &inst)| data[(p + i) % self.size] == inst)
+    }
+}
+
+/// The 64 bits of `bits` starting at bit `word * 64 + shift`, with bits past
+/// the end reading as clear
+fn shifted_word(bits: &[u64], word: usize, shift: usize) -> u64 {
+    let first = word + shift / 64;
+    let low = bits.get(first).copied().unwrap_or(0);
+    match shift % 64 {
+        0 => low,
+        s => (low >> s) | (bits.get(first + 1).copied().unwrap_or(0) << (64 - s)),
+    }
+}
+
+/// Bits of `word` that stand for cells in `lo..=hi`
+fn range_mask(word: usize, lo: usize, hi: usize) -> u64 {
+    let first = lo.saturating_sub(word * 64);
+    let last = (hi - word * 64).min(63);
+    (!0u64 << first) & (!0u64 >> (63 - last))
+}
@@ -0,0 +1,344 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// The shape of the address space, which decides where template searches
+/// look and where daughters may be placed. Cells are always stored in one
+/// buffer and the instruction pointer always moves through it in address
+/// order; only searches and placement follow the topology.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum Topology {
+    /// A line with hard ends: searches stop at them and no block straddles them
+    Linear,
+    /// A ring, as in Tierra
+    #[default]
+    Circular,
+    /// Rows of `width` cells with both axes wrapping. Searches cover the
+    /// next row's worth of cells in address order first, so that an
+    /// organism crossing a row end still finds its own templates, then
+    /// radiate a row at a time up to `radius` rows away, within `radius`
+    /// columns; daughters start within `radius` rows and columns of their
+    /// mother.
+    Torus { width: usize, radius: usize },
+    /// `count` equal rings of their own. Searches and placement stay on the
+    /// mother's island, except that with probability `migration_rate` a
+    /// daughter is placed on another island.
+    Islands { count: usize, migration_rate: f64 },
+}
+
+/// Candidate first cells `lo..=hi` of a template search, tried nearest
+/// first: upwards from `lo` or downwards from `hi`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub lo: usize,
+    pub hi: usize,
+    pub upward: bool,
+}
+
+impl Topology {
+    /// Short name used in the UI
+    pub fn name(&self) -> &'static str {
+        match self {
+            Topology::Linear => "linear",
+            Topology::Circular => "circular",
+            Topology::Torus { .. } => "torus",
+            Topology::Islands { .. } => "islands",
+        }
+    }
+
+    /// Check that the topology fits a soup of `memory_size` cells
+    pub fn check(&self, memory_size: usize) -> Result<(), String> {
+        match *self {
+            Topology::Torus { width, radius } if width == 0 || width > memory_size || radius == 0 => Err(format!(
+                "Torus width must be between 1 and the memory size ({}) and radius at least 1",
+                memory_size
+            )),
+            Topology::Islands { count, migration_rate } if count == 0 || count > memory_size || !(0.0..=1.0).contains(&migration_rate) => {
+                Err(format!(
+                    "Island count must be between 1 and the memory size ({}) and migration rate between 0 and 1",
+                    memory_size
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Distance between two cells, as searches measure it
+    pub fn distance(&self, a: usize, b: usize, size: usize) -> usize {
+        let (a, b) = (a % size, b % size);
+        let ring = |d: usize, len: usize| d.min(len - d);
+        match *self {
+            Topology::Linear => a.abs_diff(b),
+            Topology::Circular => ring(a.abs_diff(b), size),
+            Topology::Torus { width, .. } => {
+                let height = size.div_ceil(width);
+                ring((a % width).abs_diff(b % width), width).max(ring((a / width).abs_diff(b / width), height))
+            }
+            Topology::Islands { .. } => {
+                let island = self.island(self.island_of(a, size), size);
+                if island.contains(&b) {
+                    ring(a.abs_diff(b), island.len())
+                } else {
+                    a.abs_diff(b)
+                }
+            }
+        }
+    }
+
+    /// Number of islands; other topologies are one island
+    pub fn island_count(&self) -> usize {
+        match *self {
+            Topology::Islands { count, .. } => count,
+            _ => 1,
+        }
+    }
+
+    /// Island containing `addr`
+    pub fn island_of(&self, addr: usize, size: usize) -> usize {
+        let count = self.island_count();
+        // The largest i with i * size / count <= addr
+        ((addr % size + 1) * count - 1) / size
+    }
+
+    /// Cells of island `i`
+    pub fn island(&self, i: usize, size: usize) -> Range<usize> {
+        let count = self.island_count();
+        i * size / count..(i + 1) * size / count
+    }
+
+    /// Whether compaction may slide blocks to the start of the soup; a torus
+    /// or islands would carry organisms out of their neighbourhoods
+    pub fn compactable(&self) -> bool {
+        matches!(self, Topology::Linear | Topology::Circular)
+    }
+
+    /// Whether a block may occupy `len` cells from `start`
+    pub fn allows_block(&self, start: usize, len: usize, size: usize) -> bool {
+        match self {
+            Topology::Linear => start % size + len <= size,
+            Topology::Islands { .. } => self.island(self.island_of(start, size), size).end >= start % size + len,
+            Topology::Circular | Topology::Torus { .. } => true,
+        }
+    }
+
+    /// Where a template search for a complement of `len` cells looks, given
+    /// the first cell a search from `start` could match and how many cells
+    /// it may go: groups of spans, tried in order until one has a match.
+    /// Within a group the match nearest `start` wins. `None` means the whole
+    /// ring, as in Tierra.
+    pub fn search_spans(&self, start: usize, len: usize, max_search: usize, forward: bool, size: usize) -> Option<Vec<Vec<Span>>> {
+        let start = start % size;
+        let groups = match *self {
+            Topology::Circular => return None,
+            Topology::Linear => {
+                let Some(cap) = size.checked_sub(len) else {
+                    return Some(Vec::new());
+                };
+                let (first, count) = if forward {
+                    (start + 1, max_search.min(size - 1 - start))
+                } else {
+                    (start.wrapping_sub(1), max_search.min(start))
+                };
+                vec![cyclic_spans(0..size, first, count, forward, cap)]
+            }
+            Topology::Islands { .. } => {
+                let island = self.island(self.island_of(start, size), size);
+                let cap = match island.end.checked_sub(len) {
+                    Some(cap) if cap >= island.start => cap,
+                    _ => return Some(Vec::new()),
+                };
+                let count = max_search.min(island.len() - 1);
+                let first = match (forward, start + 1 == island.end, start == island.start) {
+                    (true, true, _) => island.start,
+                    (true, false, _) => start + 1,
+                    (false, _, true) => island.end - 1,
+                    (false, _, false) => start - 1,
+                };
+                // Each piece on its own, so that nearer cells in search order win
+                cyclic_spans(island, first, count, forward, cap)
+                    .into_iter()
+                    .map(|span| vec![span])
+                    .collect()
+            }
+            Topology::Torus { width, radius } => {
+                let height = size.div_ceil(width);
+                let (x, y) = (start % width, start / width);
+                let row = |y: usize| y * width..((y + 1) * width).min(size);
+                let mut groups = Vec::new();
+
+                // A row's worth of cells in address order, as on a ring
+                let reach = max_search.min(width - 1);
+                let first = if forward { (start + 1) % size } else { (start + size - 1) % size };
+                for span in cyclic_spans(0..size, first, reach, forward, size - 1) {
+                    groups.push(vec![span]);
+                }
+
+                // Then whole neighbourhoods of rows further away, nearest cell first
+                let half = radius.min((width - 1) / 2);
+                for dy in 1..=radius.min(height - 1) {
+                    let y = if forward { (y + dy) % height } else { (y + height - dy) % height };
+                    let base = y * width;
+                    let mut group = cyclic_spans(row(y), base + x, half + 1, true, size - 1);
+                    group.extend(cyclic_spans(row(y), base + (x + width - 1) % width, half, false, size - 1));
+                    groups.push(group);
+                }
+                groups
+            }
+        };
+        Some(groups)
+    }
+
+    /// Cells any block of `len` cells may start at, or `None` for anywhere
+    /// in the soup
+    pub fn block_starts(&self, len: usize, size: usize) -> Option<Vec<Range<usize>>> {
+        match self {
+            Topology::Islands { .. } => Some(
+                (0..self.island_count())
+                    .map(|i| self.island(i, size))
+                    .filter(|island| island.len() >= len)
+                    .map(|island| island.start..island.end + 1 - len)
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Cells a daughter of `len` cells may start at when her mother starts
+    /// at `home`, as address ranges, or `None` for anywhere in the soup
+    pub fn placement_starts(&self, home: usize, len: usize, size: usize) -> Option<Vec<Range<usize>>> {
+        let home = home % size;
+        match *self {
+            Topology::Linear | Topology::Circular => None,
+            Topology::Islands { .. } => {
+                let island = self.island(self.island_of(home, size), size);
+                let last = island.end.checked_sub(len).filter(|&last| last >= island.start);
+                Some(last.map(|last| island.start..last + 1).into_iter().collect())
+            }
+            Topology::Torus { width, radius } => {
+                let height = size.div_ceil(width);
+                let (x, y) = (home % width, home / width);
+                let top = (y + height - radius % height) % height;
+                let left = (x + width - radius % width) % width;
+                let columns = (2 * radius + 1).min(width);
+                let mut starts = Vec::new();
+                for dy in 0..(2 * radius + 1).min(height) {
+                    let y = (top + dy) % height;
+                    let row = y * width..((y + 1) * width).min(size);
+                    starts.extend(
+                        cyclic_spans(row, y * width + left, columns, true, size - 1)
+                            .into_iter()
+                            .map(|span| span.lo..span.hi + 1),
+                    );
+                }
+                Some(starts)
+            }
+        }
+    }
+}
+
+/// `count` cells of the ring `ring` from `first`, upwards or downwards, as
+/// at most two spans, with cells above `cap` left out
+fn cyclic_spans(ring: Range<usize>, first: usize, count: usize, upward: bool, cap: usize) -> Vec<Span> {
+    if count == 0 || !ring.contains(&first) {
+        return Vec::new();
+    }
+    let count = count.min(ring.len());
+    let mut pieces = Vec::with_capacity(2);
+    if upward {
+        let direct = count.min(ring.end - first);
+        pieces.push((first, first + direct - 1));
+        if count > direct {
+            pieces.push((ring.start, ring.start + count - direct - 1));
+        }
+    } else {
+        let direct = count.min(first + 1 - ring.start);
+        pieces.push((first + 1 - direct, first));
+        if count > direct {
+            pieces.push((ring.end - (count - direct), ring.end - 1));
+        }
+    }
+    pieces
+        .into_iter()
+        .filter(|&(lo, hi)| lo <= hi.min(cap))
+        .map(|(lo, hi)| Span { lo, hi: hi.min(cap), upward })
+        .collect()
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Topology::Linear => write!(f, "linear"),
+            Topology::Circular => write!(f, "circular"),
+            Topology::Torus { width, radius } => write!(f, "torus:{},{}", width, radius),
+            Topology::Islands { count, migration_rate } => write!(f, "islands:{},{}", count, migration_rate),
+        }
+    }
+}
+
+/// Parses `linear`, `circular`, `torus[:WIDTH[,RADIUS]]` or
+/// `islands[:COUNT[,MIGRATION_RATE]]`
+impl FromStr for Topology {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, args) = match s.split_once(':') {
+            Some((name, args)) => (name, args.split(',').collect()),
+            None => (s, Vec::new()),
+        };
+        fn arg<T: FromStr>(args: &[&str], i: usize, default: T) -> Result<T, String> {
+            match args.get(i) {
+                Some(arg) => arg.trim().parse().map_err(|_| format!("Invalid topology parameter: {}", arg)),
+                None => Ok(default),
+            }
+        }
+        match (name, args.len()) {
+            ("linear", 0) => Ok(Topology::Linear),
+            ("circular", 0) => Ok(Topology::Circular),
+            ("torus", 0..=2) => Ok(Topology::Torus { width: arg(&args, 0, 256)?, radius: arg(&args, 1, 8)? }),
+            ("islands", 0..=2) => Ok(Topology::Islands { count: arg(&args, 0, 4)?, migration_rate: arg(&args, 1, 0.01)? }),
+            _ => Err(format!("Unknown topology: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_round_trip_and_check() {
+        for text in ["linear", "circular", "torus:16,2", "islands:4,0.05"] {
+            let topology: Topology = text.parse().unwrap();
+            assert_eq!(topology.to_string(), text);
+        }
+        assert_eq!("torus".parse(), Ok(Topology::Torus { width: 256, radius: 8 }));
+        assert!("sphere".parse::<Topology>().is_err());
+        assert!("torus:x".parse::<Topology>().is_err());
+        assert!(Topology::Torus { width: 0, radius: 1 }.check(100).is_err());
+        assert!(Topology::Islands { count: 4, migration_rate: 2.0 }.check(100).is_err());
+    }
+
+    #[test]
+    fn test_islands_and_torus_geometry() {
+        let islands = Topology::Islands { count: 3, migration_rate: 0.0 };
+        assert_eq!((0..3).map(|i| islands.island(i, 100)).collect::<Vec<_>>(), vec![0..33, 33..66, 66..100]);
+        assert_eq!([0, 32, 33, 65, 66, 99].map(|addr| islands.island_of(addr, 100)), [0, 0, 1, 1, 2, 2]);
+        assert!(islands.allows_block(60, 6, 100) && !islands.allows_block(60, 7, 100));
+        assert_eq!(islands.placement_starts(40, 10, 100), Some(vec![Range { start: 33, end: 57 }]));
+        assert_eq!(islands.block_starts(10, 100), Some(vec![0..24, 33..57, 66..91]));
+        assert_eq!(islands.distance(34, 64, 100), 3);
+
+        // A 10 x 10 torus: cell 0 is the corner, neighbouring 9, 90 and 99
+        let torus = Topology::Torus { width: 10, radius: 1 };
+        assert_eq!(torus.distance(0, 99, 100), 1);
+        assert_eq!(torus.distance(0, 55, 100), 5);
+        assert_eq!(torus.placement_starts(0, 5, 100), Some(vec![99..100, 90..92, 9..10, 0..2, 19..20, 10..12]));
+
+        // Forward: the next row's worth of cells, then the row below, nearest first
+        let spans = torus.search_spans(0, 2, 200, true, 100).unwrap();
+        assert_eq!(spans[0], vec![Span { lo: 1, hi: 9, upward: true }]);
+        assert_eq!(spans[1], vec![Span { lo: 10, hi: 11, upward: true }, Span { lo: 19, hi: 19, upward: false }]);
+        assert_eq!(spans.len(), 2);
+    }
+}
@@ -1,6 +1,16 @@
+use crate::allocator::Placement;
+use crate::scheduler::SlicePolicy;
 use crate::simulator::{SimulationConfig, Simulator};
+use crate::snapshot;
+use crate::assembler;
+use crate::debugger::{Breakpoint, Debugger, StopReason};
+use crate::metrics::{Metric, MetricsSink};
 use crate::instruction::Instruction;
+use crate::isa::Isa;
+use crate::topology::Topology;
 use egui::{Color32, Pos2, Rect, Stroke, Vec2};
+use std::fs::File;
+use std::io::BufWriter;
 
 pub struct TierraApp {
     pub simulator: Simulator,
@@ -9,12 +19,25 @@ pub struct TierraApp {
     pub config: SimulationConfig,
     pub memory_view_offset: usize,
     pub memory_view_size: usize,
+    pub snapshot_path: String,
+    pub genome_path: String,
+    pub inoculate_count: usize,
+    pub inoculate_at_address: bool,
+    pub inoculate_address: usize,
+    pub lineage_path: String,
+    pub debugger: Debugger,
+    pub metrics_path: String,
+    pub metrics_every: u64,
+    pub metrics_sink: Option<MetricsSink<BufWriter<File>>>,
+    pub break_address: usize,
+    pub break_instruction: Instruction,
+    pub status_message: Option<String>,
 }
 
 impl Default for TierraApp {
     fn default() -> Self {
         let config = SimulationConfig::default();
-        let mut simulator = Simulator::new(config.clone());
+        let mut simulator = Simulator::new(config.clone()).expect("the default config is valid");
         simulator.initialize_with_ancestor();
 
         Self {
@@ -24,6 +47,188 @@ impl Default for TierraApp {
             config,
             memory_view_offset: 0,
             memory_view_size: 256,
+            snapshot_path: "tierra_snapshot.json".to_string(),
+            genome_path: "genomes/ancestor.tie".to_string(),
+            inoculate_count: 1,
+            inoculate_at_address: false,
+            inoculate_address: 0,
+            lineage_path: "lineage.nwk".to_string(),
+            debugger: Debugger::new(),
+            metrics_path: "metrics.csv".to_string(),
+            metrics_every: 10_000,
+            metrics_sink: None,
+            break_address: 0,
+            break_instruction: Instruction::Divide,
+            status_message: None,
+        }
+    }
+}
+
+/// Instructions the debugger may run before giving up on reaching Divide
+const DEBUG_RUN_LIMIT: usize = 100_000;
+
+impl TierraApp {
+    /// Debugger window for the selected organism
+    fn show_debugger(&mut self, ctx: &egui::Context) {
+        let Some(id) = self.debugger.organism else {
+            return;
+        };
+        let mut open = true;
+
+        egui::Window::new(format!("Debugger - organism {}", id))
+            .open(&mut open)
+            .default_width(320.0)
+            .show(ctx, |ui| {
+                let Some(organism) = self.simulator.organisms.iter().find(|o| o.id == id && o.alive) else {
+                    ui.label("This organism is dead.");
+                    return;
+                };
+                let ip = organism.ip;
+                let isa = self.simulator.cpu.isa;
+
+                ui.label(format!("Size: {}  Gen: {}  Addr: {:#x}  Errors: {}",
+                    organism.size, organism.generation, organism.address, organism.errors));
+                ui.monospace(format!("IP {:#06x}  AX {:#06x}  BX {:#06x}", ip, organism.ax, organism.bx));
+                ui.monospace(format!("CX {:#06x}  DX {:#06x}", organism.cx, organism.dx));
+                let stack: Vec<String> = organism.stack.iter().rev().map(|v| format!("{:#x}", v)).collect();
+                ui.monospace(format!("Stack: [{}]", stack.join(", ")));
+                if let Some(daughter) = organism.daughter {
+                    ui.monospace(format!("Daughter: {:#x} ({} instructions, {} written)",
+                        daughter.address, daughter.size, daughter.written));
+                }
+
+                ui.horizontal(|ui| {
+                    let mut reason = None;
+                    if ui.button("⏭ Step").clicked() {
+                        reason = Some(self.debugger.step(&mut self.simulator));
+                    }
+                    if ui.button("⏩ Run to divide").clicked() {
+                        reason = Some(self.debugger.run_until_divide(&mut self.simulator, DEBUG_RUN_LIMIT));
+                    }
+                    if let Some(reason) = reason {
+                        self.auto_run = false;
+                        self.status_message = match reason {
+                            StopReason::Stepped => None,
+                            StopReason::Breakpoint(breakpoint) => Some(format!("Stopped at {:?}", breakpoint)),
+                            StopReason::Divided => Some(format!("Organism {} divided", id)),
+                            StopReason::Dead => Some(format!("Organism {} died", id)),
+                            StopReason::Limit => Some(format!("No divide within {} instructions", DEBUG_RUN_LIMIT)),
+                        };
+                    }
+                });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut self.break_address).range(0..=self.simulator.memory.size() - 1).hexadecimal(4, false, false));
+                    if ui.button("Break at address").clicked() {
+                        self.debugger.toggle_breakpoint(Breakpoint::Address(self.break_address));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt("break_instruction")
+                        .selected_text(isa.mnemonic(self.break_instruction))
+                        .show_ui(ui, |ui| {
+                            for inst in isa.instructions() {
+                                ui.selectable_value(&mut self.break_instruction, inst, isa.mnemonic(inst));
+                            }
+                        });
+                    if ui.button("Break on instruction").clicked() {
+                        self.debugger.toggle_breakpoint(Breakpoint::Instruction(self.break_instruction));
+                    }
+                });
+                let mut remove = None;
+                for &breakpoint in &self.debugger.breakpoints {
+                    ui.horizontal(|ui| {
+                        let label = match breakpoint {
+                            Breakpoint::Address(addr) => format!("● {:#06x}", addr),
+                            Breakpoint::Instruction(inst) => format!("● {}", isa.mnemonic(inst)),
+                        };
+                        ui.label(label);
+                        if ui.small_button("✖").clicked() {
+                            remove = Some(breakpoint);
+                        }
+                    });
+                }
+                if let Some(breakpoint) = remove {
+                    self.debugger.toggle_breakpoint(breakpoint);
+                }
+
+                ui.separator();
+                ui.label("Disassembly (click a line to toggle a breakpoint):");
+                let mut toggle = None;
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for (addr, inst) in self.debugger.disassemble(&self.simulator) {
+                        let marker = if self.debugger.breakpoints.contains(&Breakpoint::Address(addr)) { "●" } else { " " };
+                        let pointer = if addr == ip { "▶" } else { " " };
+                        let text = egui::RichText::new(format!("{}{} {:#06x}  {}", marker, pointer, addr, isa.mnemonic(inst)))
+                            .monospace()
+                            .color(instruction_to_color(inst));
+                        let text = if addr == ip { text.background_color(Color32::from_gray(70)) } else { text };
+                        let response = ui.add(egui::Label::new(text).sense(egui::Sense::click()));
+                        if addr == ip {
+                            response.scroll_to_me(None);
+                        }
+                        if response.clicked() {
+                            toggle = Some(addr);
+                        }
+                    }
+                });
+                if let Some(addr) = toggle {
+                    self.debugger.toggle_breakpoint(Breakpoint::Address(addr));
+                }
+            });
+
+        if !open {
+            self.debugger.organism = None;
+        }
+    }
+
+    /// Histogram of executions per opcode, busiest first. The red part of a
+    /// bar is the share of executions whose template search failed.
+    fn show_opcode_profile(&self, ui: &mut egui::Ui) {
+        let profile = &self.simulator.stats.profile;
+        let isa = self.simulator.cpu.isa;
+        let busiest = &profile.busiest;
+        let Some(max) = busiest.first().map(|(_, c)| c.executions) else {
+            ui.label("No instructions profiled yet");
+            return;
+        };
+
+        let total = profile.total;
+        ui.label(format!("Errors: {}  Failed searches: {} / {}", total.errors, total.failed_searches, total.searches));
+
+        let row_height = 14.0;
+        let label_width = 60.0;
+        for (inst, counters) in busiest.iter().take(16) {
+            let (response, painter) = ui.allocate_painter(Vec2::new(ui.available_width(), row_height), egui::Sense::hover());
+            let rect = response.rect;
+            painter.text(
+                rect.left_center(),
+                egui::Align2::LEFT_CENTER,
+                isa.mnemonic(*inst),
+                egui::FontId::monospace(10.0),
+                Color32::WHITE,
+            );
+
+            let bar_width = (rect.width() - label_width) * counters.executions as f32 / max as f32;
+            let bar = Rect::from_min_size(
+                Pos2::new(rect.min.x + label_width, rect.min.y + 2.0),
+                Vec2::new(bar_width, row_height - 4.0),
+            );
+            painter.rect_filled(bar, 0.0, instruction_to_color(*inst));
+            let failed_width = bar_width * counters.failed_searches as f32 / counters.executions as f32;
+            painter.rect_filled(
+                Rect::from_min_size(bar.min, Vec2::new(failed_width, bar.height())),
+                0.0,
+                Color32::RED,
+            );
+
+            let distance = counters.average_search_distance().map_or("-".to_string(), |d| format!("{:.1}", d));
+            response.on_hover_text(format!(
+                "{}: {} executions, {} errors\n{} searches, {} failed, average distance {}",
+                isa.mnemonic(*inst), counters.executions, counters.errors,
+                counters.searches, counters.failed_searches, distance
+            ));
         }
     }
 }
@@ -32,7 +237,19 @@ impl eframe::App for TierraApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Auto-run simulation
         if self.auto_run {
-            self.simulator.run_steps(self.steps_per_frame);
+            match &mut self.metrics_sink {
+                Some(sink) => {
+                    for _ in 0..self.steps_per_frame {
+                        self.simulator.step();
+                        if let Err(e) = sink.maybe_sample(&self.simulator) {
+                            self.status_message = Some(format!("Failed to write metrics: {}", e));
+                            self.metrics_sink = None;
+                            break;
+                        }
+                    }
+                }
+                None => self.simulator.run_steps(self.steps_per_frame),
+            }
             ctx.request_repaint();
         }
 
@@ -52,8 +269,10 @@ impl eframe::App for TierraApp {
                 }
 
                 if ui.button("🔄 Reset").clicked() {
-                    self.simulator.reset();
-                    self.simulator.initialize_with_ancestor();
+                    match self.simulator.reset() {
+                        Ok(()) => self.simulator.initialize_with_ancestor(),
+                        Err(message) => self.status_message = Some(message),
+                    }
                     self.auto_run = false;
                 }
 
@@ -61,10 +280,60 @@ impl eframe::App for TierraApp {
 
                 ui.label("Steps/frame:");
                 ui.add(egui::Slider::new(&mut self.steps_per_frame, 1..=1000).logarithmic(true));
+
+                ui.separator();
+
+                ui.label("Snapshot:");
+                ui.add(egui::TextEdit::singleline(&mut self.snapshot_path).desired_width(180.0));
+
+                if ui.button("💾 Save").clicked() {
+                    self.status_message = Some(match snapshot::save(&self.simulator, &self.snapshot_path) {
+                        Ok(()) => format!("Saved {}", self.snapshot_path),
+                        Err(message) => message,
+                    });
+                }
+
+                if ui.button("📂 Load").clicked() {
+                    self.status_message = Some(match snapshot::load(&self.snapshot_path) {
+                        Ok(simulator) => {
+                            self.config = simulator.config.clone();
+                            self.simulator = simulator;
+                            self.auto_run = false;
+                            format!("Loaded {}", self.snapshot_path)
+                        }
+                        Err(message) => message,
+                    });
+                }
+
+                ui.separator();
+
+                ui.label("Genome:");
+                ui.add(egui::TextEdit::singleline(&mut self.genome_path).desired_width(180.0));
+
+                if ui.button("🧬 Seed").clicked() {
+                    self.status_message = Some(match assembler::load_genome(&self.genome_path, self.simulator.config.isa) {
+                        Ok(genome) => {
+                            self.auto_run = false;
+                            match self.simulator.reset() {
+                                Ok(()) => match self.simulator.initialize_with_genome(&genome) {
+                                    Some(_) => format!("Seeded {} ({} instructions)", self.genome_path, genome.len()),
+                                    None => format!("No room for {}", self.genome_path),
+                                },
+                                Err(message) => message,
+                            }
+                        }
+                        Err(message) => message,
+                    });
+                }
+
+                if let Some(message) = &self.status_message {
+                    ui.label(message);
+                }
             });
         });
 
         // Left panel - statistics
+        let mut compact_now = false;
         egui::SidePanel::left("stats_panel").min_width(250.0).show(ctx, |ui| {
             ui.heading("Statistics");
             ui.separator();
@@ -74,12 +343,21 @@ impl eframe::App for TierraApp {
             ui.label(format!("Population: {}", stats.current_population));
             ui.label(format!("Total Instructions: {}", stats.total_instructions));
             ui.label(format!("Total Born: {}", stats.total_organisms_created));
-            ui.label(format!("Total Died: {}", stats.total_organisms_died));
+            ui.label(format!("Total Died: {} (reaped {})", stats.total_organisms_died, stats.reaper_kills));
             ui.label(format!("Mutations: {}", stats.total_mutations));
+            ui.label(format!("  Copy: {}  Cosmic: {}  Flaw: {}",
+                stats.copy_mutations, stats.cosmic_ray_mutations, stats.flaws));
+            ui.label(format!("  Ins: {}  Del: {}  Cross: {}",
+                stats.insertions, stats.deletions, stats.crossovers));
 
             ui.separator();
 
             ui.label(format!("Memory: {:.1}%", stats.memory_usage_percent()));
+            ui.label(format!("  Largest free: {}  Holes: {}", stats.largest_free_block, stats.free_blocks));
+            ui.label(format!("  Fragmentation: {:.1}%  Compactions: {}", stats.fragmentation() * 100.0, stats.compactions));
+            if matches!(self.simulator.memory.topology(), Topology::Islands { .. }) {
+                ui.label(format!("  Migrations: {}", stats.migrations));
+            }
             ui.label(format!("Replications: {} / {}",
                 stats.successful_replications,
                 stats.successful_replications + stats.failed_replications
@@ -93,6 +371,66 @@ impl eframe::App for TierraApp {
             }
             ui.label(format!("Highest Generation: {}", stats.highest_generation()));
 
+            let diversity = &stats.diversity;
+            ui.label(format!("Richness: {}  Size classes: {}", diversity.richness, diversity.size_classes));
+            ui.label(format!("Shannon: {:.3}  Simpson: {:.3}", diversity.shannon, diversity.simpson));
+            ui.label(format!("Dominant share: {:.1}%", diversity.dominant_share * 100.0));
+            ui.label(format!("Turnover: {:.1}% (+{} / -{})",
+                diversity.turnover * 100.0, diversity.appeared, diversity.extinct));
+
+            ui.separator();
+
+            let interactions = &self.simulator.interactions;
+            let genebank = &self.simulator.genebank;
+            ui.label(format!("Roles: {}", interactions.summary(genebank)));
+            let name = |id| genebank.get(id).map_or("?", |g| g.name.as_str());
+            for (user, provider, count) in interactions.strongest(3) {
+                ui.label(format!("  {} uses {}  x{}", name(user), name(provider), count));
+            }
+
+            ui.separator();
+
+            let genebank = &self.simulator.genebank;
+            ui.label(format!("Genotypes: {} living / {} seen", genebank.living_count(), genebank.len()));
+            for genotype in genebank.most_abundant(5) {
+                ui.label(format!("  {}  x{} (peak {})", genotype.name, genotype.abundance, genotype.peak_abundance));
+            }
+
+            ui.label("Metrics:");
+            ui.horizontal(|ui| {
+                ui.add_enabled(
+                    self.metrics_sink.is_none(),
+                    egui::TextEdit::singleline(&mut self.metrics_path).desired_width(140.0),
+                );
+                ui.add_enabled(self.metrics_sink.is_none(), egui::DragValue::new(&mut self.metrics_every).range(100..=10_000_000))
+                    .on_hover_text("Instructions between samples");
+                if let Some(sink) = &mut self.metrics_sink {
+                    if ui.button("⏹ Stop").clicked() {
+                        self.status_message = Some(match sink.finish(&self.simulator) {
+                            Ok(()) => format!("Wrote {}", self.metrics_path),
+                            Err(e) => format!("Failed to write metrics: {}", e),
+                        });
+                        self.metrics_sink = None;
+                    }
+                } else if ui.button("⏺ Record").clicked() {
+                    match MetricsSink::create(&self.metrics_path, Metric::ALL.to_vec(), self.metrics_every) {
+                        Ok(sink) => self.metrics_sink = Some(sink),
+                        Err(message) => self.status_message = Some(message),
+                    }
+                }
+            });
+
+            ui.label(format!("Lineage: {} nodes", self.simulator.lineage.len()));
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.lineage_path).desired_width(140.0));
+                if ui.button("🌳 Export").clicked() {
+                    self.status_message = Some(match self.simulator.lineage.save(&self.simulator.genebank, &self.lineage_path) {
+                        Ok(()) => format!("Wrote {}", self.lineage_path),
+                        Err(message) => message,
+                    });
+                }
+            });
+
             ui.separator();
             ui.heading("Configuration");
 
@@ -101,12 +439,125 @@ impl eframe::App for TierraApp {
                 // Mutation rate changed
             }
 
+            let config = &mut self.simulator.config;
+            ui.add(egui::Slider::new(&mut config.cosmic_ray_rate, 0.0..=0.001).logarithmic(true).text("Cosmic Ray"));
+            ui.add(egui::Slider::new(&mut config.flaw_rate, 0.0..=0.01).logarithmic(true).text("Flaw"));
+            ui.add(egui::Slider::new(&mut config.insertion_rate, 0.0..=1.0).text("Insertion"));
+            ui.add(egui::Slider::new(&mut config.deletion_rate, 0.0..=1.0).text("Deletion"));
+            ui.add(egui::Slider::new(&mut config.crossover_rate, 0.0..=1.0).text("Crossover"));
+
+            ui.add(egui::Slider::new(&mut config.reaper_threshold, 0.1..=1.0).text("Reaper"));
+            ui.add(egui::Slider::new(&mut config.divide_min_written, 0.0..=1.0).text("Divide Written"))
+                .on_hover_text("Fraction of the daughter block that must be written before Divide succeeds");
+
             ui.label(format!("Max Population: {}", self.simulator.config.max_population));
             ui.add(egui::Slider::new(&mut self.simulator.config.max_population, 10..=500).text("Max Pop"));
 
             ui.label(format!("Time Slice: {}", self.simulator.config.time_slice));
             ui.add(egui::Slider::new(&mut self.simulator.config.time_slice, 1..=100).text("Time Slice"));
 
+            let policy = &mut self.simulator.config.slice_policy;
+            egui::ComboBox::from_label("Slicing")
+                .selected_text(policy.name())
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(policy, SlicePolicy::Fixed, "fixed");
+                    if ui.selectable_label(matches!(policy, SlicePolicy::SizePower { .. }), "size").clicked() {
+                        *policy = SlicePolicy::SizePower { power: 1.0 };
+                    }
+                    if ui.selectable_label(matches!(policy, SlicePolicy::Jitter { .. }), "jitter").clicked() {
+                        *policy = SlicePolicy::Jitter { fraction: 0.5 };
+                    }
+                });
+            match policy {
+                SlicePolicy::Fixed => {}
+                SlicePolicy::SizePower { power } => {
+                    ui.add(egui::Slider::new(power, 0.0..=2.0).text("Size Power"));
+                }
+                SlicePolicy::Jitter { fraction } => {
+                    ui.add(egui::Slider::new(fraction, 0.0..=1.0).text("Jitter"));
+                }
+            }
+
+            let placement = &mut self.simulator.config.placement;
+            egui::ComboBox::from_label("Placement")
+                .selected_text(placement.name())
+                .show_ui(ui, |ui| {
+                    for option in Placement::ALL {
+                        ui.selectable_value(placement, option, option.name());
+                    }
+                })
+                .response
+                .on_hover_text("Where MallocA places daughter blocks");
+            ui.horizontal(|ui| {
+                let toggled = ui.checkbox(&mut self.simulator.config.compaction, "Compaction")
+                    .on_hover_text("Compact the soup before reaping when fragmentation blocks a MallocA")
+                    .changed();
+                if toggled && self.simulator.config.compaction && !self.simulator.memory.topology().compactable() {
+                    self.status_message = Some("Compaction needs a linear or circular topology".to_string());
+                }
+                compact_now = ui.button("🧹 Compact now").clicked();
+            });
+
+            let memory_size = self.simulator.config.memory_size;
+            let topology = &mut self.simulator.config.topology;
+            egui::ComboBox::from_label("Topology")
+                .selected_text(topology.name())
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(topology, Topology::Linear, "linear");
+                    ui.selectable_value(topology, Topology::Circular, "circular");
+                    if ui.selectable_label(matches!(topology, Topology::Torus { .. }), "torus").clicked() {
+                        *topology = Topology::Torus { width: 256, radius: 8 };
+                    }
+                    if ui.selectable_label(matches!(topology, Topology::Islands { .. }), "islands").clicked() {
+                        *topology = Topology::Islands { count: 4, migration_rate: 0.01 };
+                    }
+                })
+                .response
+                .on_hover_text("Shape of the address space; takes effect on Reset");
+            match topology {
+                Topology::Linear | Topology::Circular => {}
+                Topology::Torus { width, radius } => {
+                    ui.add(egui::Slider::new(width, 1..=memory_size).logarithmic(true).text("Width"));
+                    ui.add(egui::Slider::new(radius, 1..=256).text("Radius"));
+                }
+                Topology::Islands { count, migration_rate } => {
+                    ui.add(egui::Slider::new(count, 1..=64).text("Islands"));
+                    ui.add(egui::Slider::new(migration_rate, 0.0..=1.0).logarithmic(true).text("Migration"));
+                }
+            }
+
+            let isa = &mut self.simulator.config.isa;
+            egui::ComboBox::from_label("ISA")
+                .selected_text(isa.name())
+                .show_ui(ui, |ui| {
+                    for option in Isa::ALL {
+                        ui.selectable_value(isa, option, option.name());
+                    }
+                })
+                .response
+                .on_hover_text("Instruction set; takes effect on Reset");
+
+            ui.label("Permissions");
+            let permissions = &mut self.simulator.config.permissions;
+            egui::Grid::new("permissions").show(ui, |ui| {
+                ui.label("");
+                for heading in ["R", "W", "X"] {
+                    ui.label(heading);
+                }
+                ui.end_row();
+                for (name, access) in [
+                    ("Own", &mut permissions.own),
+                    ("Daughter", &mut permissions.daughter),
+                    ("Foreign", &mut permissions.foreign),
+                ] {
+                    ui.label(name);
+                    ui.checkbox(&mut access.read, "");
+                    ui.checkbox(&mut access.write, "");
+                    ui.checkbox(&mut access.execute, "");
+                    ui.end_row();
+                }
+            });
+
             ui.separator();
             ui.heading("Population Graph");
 
@@ -143,13 +594,50 @@ impl eframe::App for TierraApp {
                     Color32::WHITE,
                 );
             }
+
+            ui.separator();
+            egui::CollapsingHeader::new("Opcode Profile").show(ui, |ui| {
+                self.show_opcode_profile(ui);
+            });
         });
+        if compact_now {
+            if self.simulator.memory.topology().compactable() {
+                self.simulator.compact();
+            } else {
+                self.status_message = Some("Compaction needs a linear or circular topology".to_string());
+            }
+        }
 
         // Right panel - organisms list
         egui::SidePanel::right("organisms_panel").min_width(200.0).show(ctx, |ui| {
             ui.heading("Organisms");
             ui.separator();
 
+            ui.label("Inoculate genome file:");
+            ui.horizontal(|ui| {
+                ui.label("Count:");
+                ui.add(egui::DragValue::new(&mut self.inoculate_count).range(1..=100));
+                ui.checkbox(&mut self.inoculate_at_address, "At:");
+                ui.add_enabled(
+                    self.inoculate_at_address,
+                    egui::DragValue::new(&mut self.inoculate_address).range(0..=self.simulator.memory.size() - 1),
+                );
+            });
+            if ui.button("💉 Inoculate").clicked() {
+                self.status_message = Some(match assembler::load_genome(&self.genome_path, self.simulator.cpu.isa) {
+                    Ok(genome) => {
+                        let hint = self.inoculate_at_address.then_some(self.inoculate_address);
+                        let placed = self.simulator.inoculate(&genome, hint, self.inoculate_count);
+                        format!("Inoculated {}/{} of {}", placed.len(), self.inoculate_count, self.genome_path)
+                    }
+                    Err(message) => message,
+                });
+            }
+
+            ui.separator();
+
+            let mut clone_request = None;
+            let mut debug_request = None;
             egui::ScrollArea::vertical().show(ui, |ui| {
                 let mut organisms: Vec<_> = self.simulator.organisms.iter()
                     .filter(|o| o.alive)
@@ -165,6 +653,14 @@ impl eframe::App for TierraApp {
                         ui.label(format!("Addr: {:#x}", organism.address));
                         ui.label(format!("Cycles: {}", organism.cycles));
                         ui.label(format!("Errors: {}", organism.errors));
+                        ui.horizontal(|ui| {
+                            if ui.small_button("Clone").on_hover_text("Inoculate another copy of this genome").clicked() {
+                                clone_request = Some((organism.address, organism.size));
+                            }
+                            if ui.small_button("🐞 Debug").clicked() {
+                                debug_request = Some(organism.id);
+                            }
+                        });
                     });
                 }
 
@@ -172,6 +668,17 @@ impl eframe::App for TierraApp {
                     ui.label(format!("... and {} more", organisms.len() - 50));
                 }
             });
+
+            if let Some(id) = debug_request {
+                self.debugger.organism = Some(id);
+                self.auto_run = false;
+            }
+
+            if let Some((address, size)) = clone_request {
+                let genome = self.simulator.memory.get_slice(address, size);
+                let placed = self.simulator.inoculate(&genome, None, 1);
+                self.status_message = Some(format!("Cloned {} instructions into {} organism(s)", size, placed.len()));
+            }
         });
 
         // Central panel - memory visualization
@@ -191,7 +698,11 @@ impl eframe::App for TierraApp {
             // Draw memory as a grid
             let available_size = ui.available_size();
             let cell_size = 4.0;
-            let cells_per_row = (available_size.x / cell_size).floor() as usize;
+            // A torus is drawn a row of the torus per row, when one fits
+            let cells_per_row = match self.simulator.memory.topology() {
+                Topology::Torus { width, .. } if width as f32 * cell_size <= available_size.x => width,
+                _ => (available_size.x / cell_size).floor() as usize,
+            };
 
             if cells_per_row > 0 {
                 let (response, painter) = ui.allocate_painter(
@@ -262,6 +773,8 @@ impl eframe::App for TierraApp {
                 ui.colored_label(Color32::from_rgb(255, 200, 100), "■ Stack");
             });
         });
+
+        self.show_debugger(ctx);
     }
 }
 
@@ -269,11 +782,13 @@ impl eframe::App for TierraApp {
 fn instruction_to_color(inst: Instruction) -> Color32 {
     match inst {
         Instruction::Nop0 | Instruction::Nop1 => Color32::from_gray(60),
-        Instruction::IfCZ | Instruction::JmpB | Instruction::JmpF | Instruction::Call | Instruction::Ret =>
+        Instruction::IfCZ | Instruction::JmpB | Instruction::JmpF | Instruction::JmpO | Instruction::Call | Instruction::Ret =>
             Color32::from_rgb(100, 150, 255),
-        Instruction::MovDC | Instruction::MovCD | Instruction::Adr | Instruction::AdrB | Instruction::AdrF =>
+        Instruction::MovDC | Instruction::MovCD | Instruction::MovII | Instruction::MovAB | Instruction::MovBA | Instruction::CopyDC |
+        Instruction::Adr | Instruction::AdrB | Instruction::AdrF | Instruction::AdrO =>
             Color32::from_rgb(100, 255, 100),
-        Instruction::IncA | Instruction::IncB | Instruction::IncC | Instruction::DecC =>
+        Instruction::IncA | Instruction::IncB | Instruction::IncC | Instruction::DecC |
+        Instruction::SubAB | Instruction::SubAC | Instruction::Shl | Instruction::Not0 | Instruction::Zero =>
             Color32::from_rgb(255, 100, 100),
         Instruction::MallocA | Instruction::Divide =>
             Color32::from_rgb(255, 255, 100),